@@ -0,0 +1,88 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Helpers shared by the fuzz targets. Not every target uses every helper.
+#![allow(dead_code)]
+
+use deb_rust::binary::{DebArchive, DebReader};
+use deb_rust::DebLimits;
+
+// Keeps inputs which decompress to huge archives from running out of memory,
+// which would be reported as a crash
+pub fn limits() -> DebLimits {
+    DebLimits::new()
+        .set_max_member_size(16 * 1024 * 1024)
+        .set_max_decompressed_size(64 * 1024 * 1024)
+        .set_max_entries(10_000)
+}
+
+// Reads `deb` both in memory and lazily. Errors are expected, panics aren't.
+pub fn read_deb(deb: &[u8]) {
+    if let Ok(archive) = DebArchive::read_with_limits(deb, limits()) {
+        let _ = archive.to_package();
+    }
+    if let Ok(mut reader) = DebReader::with_limits(deb, limits()) {
+        if let Ok(entries) = reader.entries() {
+            for entry in entries.flatten() {
+                let _ = entry.into_file();
+            }
+        }
+    }
+}
+
+// Builds a .deb with uncompressed `control` and `data` archives, so the fuzzer
+// reaches the tar and control parsers directly
+pub fn deb_from_archives(control: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut output = b"!<arch>\n".to_vec();
+    for (name, contents) in [
+        ("debian-binary", b"2.0\n".as_slice()),
+        ("control.tar", control),
+        ("data.tar", data),
+    ] {
+        output.extend(
+            format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}`\n",
+                name,
+                0,
+                0,
+                0,
+                33188,
+                contents.len()
+            )
+            .as_bytes(),
+        );
+        output.extend(contents);
+        if contents.len() % 2 == 1 {
+            output.push(b'\n');
+        }
+    }
+    output
+}
+
+// Builds an uncompressed tar archive containing a single file
+pub fn tar_with_file(path: &str, contents: &[u8]) -> Vec<u8> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    let mut builder = tar::Builder::new(Vec::new());
+    builder
+        .append_data(&mut header, path, contents)
+        .expect("writing to a Vec can't fail");
+    builder.into_inner().expect("writing to a Vec can't fail")
+}
@@ -0,0 +1,35 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Fuzzes the control file and deb822 parsers
+
+#![no_main]
+
+mod common;
+
+use deb_rust::DebParagraph;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = DebParagraph::parse_all(input);
+    }
+    let control = common::tar_with_file("control", data);
+    let data = common::tar_with_file("./usr/share/fuzz", b"");
+    common::read_deb(&common::deb_from_archives(&control, &data));
+});
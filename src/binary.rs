@@ -28,14 +28,14 @@
 //! use deb_rust::*;
 //! use deb_rust::binary::*;
 //!
-//! fn main() -> std::io::Result<()> {
+//! fn main() -> Result<(), DebError> {
 //!     let mut package = DebPackage::new("example");
 //!
 //!     package = package
-//!         .set_version("0.1.0")
+//!         .set_version(DebVersion::from("0.1.0")?)
 //!         .set_description("deb-rust example")
 //!         .set_architecture(DebArchitecture::Amd64)
-//!         .with_depend("bash")
+//!         .with_depend(DebRelation::from("bash")?)
 //!         .with_file(DebFile::from_path(
 //!             "target/release/example",
 //!             "/usr/bin/example",
@@ -47,67 +47,112 @@
 //! }
 //! ```
 
+use crate::compression::*;
+use crate::deb822::*;
+use crate::error::*;
+use crate::openpgp::*;
+use crate::relation::*;
 use crate::shared::*;
+use crate::shlibs::*;
+use crate::symbols::*;
+use crate::templates::*;
+use crate::triggers::*;
+use crate::version::*;
 
-use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs;
-use std::io::{Error, ErrorKind, Read, Write};
-use std::path::{Path, PathBuf};
-
-use regex::Regex;
-use xz::read::XzDecoder;
-use xz::write::XzEncoder;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
 
 // Used in DebPackage to store a package's metadata
 // More about these fields here:
 // https://www.debian.org/doc/debian-policy/ch-controlfields.html#binary-package-control-files-debian-control
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct DebControl {
     name: String,
-    version: String,
+    source: String,
+    source_version: Option<DebVersion>, // Only set if it differs from the package's version
+    version: Option<DebVersion>,
+    section: String,
     priority: DebPriority,
     architecture: DebArchitecture,
+    multi_arch: Option<DebMultiArch>,
     essential: bool,
-    depends: Vec<String>,
-    pre_depends: Vec<String>,
-    recommends: Vec<String>,
-    suggests: Vec<String>,
-    breaks: Vec<String>,
-    conflicts: Vec<String>,
-    provides: Vec<String>,
-    replaces: Vec<String>,
-    enhances: Vec<String>,
+    protected: bool,
+    build_essential: bool,
+    installed_size: Option<u64>, // In kibibytes
+    installed_size_set: bool,    // Whether installed_size was set manually rather than read
+    depends: Vec<DebRelation>,
+    pre_depends: Vec<DebRelation>,
+    recommends: Vec<DebRelation>,
+    suggests: Vec<DebRelation>,
+    breaks: Vec<DebRelation>,
+    conflicts: Vec<DebRelation>,
+    provides: Vec<DebRelation>,
+    replaces: Vec<DebRelation>,
+    enhances: Vec<DebRelation>,
     maintainer: String,
     description: String,
     homepage: String,
-    built_using: Vec<[String; 2]>,
+    built_using: Vec<DebRelation>,
+    tags: Vec<String>,
+    extra: DebParagraph, // Fields not covered above, such as X- fields
+}
+
+// Converts a list of relations to the comma-separated form used in control files
+fn join_relations(relations: &[DebRelation]) -> String {
+    relations
+        .iter()
+        .map(|relation| relation.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 impl DebControl {
     // Converts DebControl into a dpkg-readable control file
     fn serialize(&self) -> Vec<u8> {
-        let mut write_out = String::new();
+        self.to_paragraph().serialize().into_bytes()
+    }
+
+    // Converts DebControl into a deb822 paragraph, with fields in the order
+    // dpkg writes them
+    fn to_paragraph(&self) -> DebParagraph {
         // Binding temporary values to longer living variables
-        let depends = self.depends.join(", ");
-        let pre_depends = self.pre_depends.join(", ");
-        let recommends = self.recommends.join(", ");
-        let suggests = self.suggests.join(", ");
-        let breaks = self.breaks.join(", ");
-        let conflicts = self.conflicts.join(", ");
-        let provides = self.provides.join(", ");
-        let enhances = self.enhances.join(", ");
-        let built_using = {
-            let mut output: Vec<String> = Vec::new();
-            for build_depend in &self.built_using {
-                output.push(format!("{} (= {})", build_depend[0], build_depend[1]));
-            }
-            output.join(", ")
+        let source = match &self.source_version {
+            Some(version) if !self.source.is_empty() => format!("{} ({})", self.source, version),
+            _ => self.source.clone(),
+        };
+        let version = match &self.version {
+            Some(version) => version.to_string(),
+            None => String::new(),
+        };
+        let multi_arch = match &self.multi_arch {
+            Some(multi_arch) => multi_arch.as_str(),
+            None => "",
         };
+        let installed_size = match self.installed_size {
+            Some(size) => size.to_string(),
+            None => String::new(),
+        };
+        let tags = self.tags.join(", ");
+        let depends = join_relations(&self.depends);
+        let pre_depends = join_relations(&self.pre_depends);
+        let recommends = join_relations(&self.recommends);
+        let suggests = join_relations(&self.suggests);
+        let breaks = join_relations(&self.breaks);
+        let conflicts = join_relations(&self.conflicts);
+        let provides = join_relations(&self.provides);
+        let replaces = join_relations(&self.replaces);
+        let enhances = join_relations(&self.enhances);
+        let built_using = join_relations(&self.built_using);
         let control = vec![
             ["Package", self.name.as_str()],
-            ["Version", self.version.as_str()],
+            ["Source", source.as_str()],
+            ["Version", version.as_str()],
+            ["Section", self.section.as_str()],
             ["Priority", self.priority.as_str()],
             ["Architecture", self.architecture.as_str()],
+            ["Multi-Arch", multi_arch],
             [
                 "Essential",
                 match self.essential {
@@ -115,6 +160,21 @@ impl DebControl {
                     false => "no",
                 },
             ],
+            [
+                "Protected",
+                match self.protected {
+                    true => "yes",
+                    false => "",
+                },
+            ],
+            [
+                "Build-Essential",
+                match self.build_essential {
+                    true => "yes",
+                    false => "",
+                },
+            ],
+            ["Installed-Size", installed_size.as_str()],
             ["Depends", depends.as_str()],
             ["Pre-Depends", pre_depends.as_str()],
             ["Recommends", recommends.as_str()],
@@ -122,22 +182,51 @@ impl DebControl {
             ["Breaks", breaks.as_str()],
             ["Conflicts", conflicts.as_str()],
             ["Provides", provides.as_str()],
+            ["Replaces", replaces.as_str()],
             ["Enhances", enhances.as_str()],
             ["Maintainer", self.maintainer.as_str()],
             ["Description", self.description.as_str()],
             ["Homepage", self.homepage.as_str()],
             ["Built-Using", built_using.as_str()],
+            ["Tag", tags.as_str()],
         ];
+        let mut paragraph = DebParagraph::new();
         for field in control {
             if !field[1].is_empty() {
-                write_out = format!("{}{}: {}\n", write_out, field[0], field[1]);
+                paragraph.set(field[0], field[1]);
+            }
+        }
+        // Additional fields are written after the known ones, with their values
+        // untouched. Known fields always take precedence over additional ones.
+        for (name, value) in self.extra.fields() {
+            if paragraph.get(name).is_none() {
+                paragraph.set(name, value);
             }
         }
-        write_out.into_bytes()
+        paragraph
     }
 
     // Converts a dpkg-readable control file into DebControl
-    fn deserialize(control: Vec<u8>) -> std::io::Result<Self> {
+    fn deserialize(control: Vec<u8>) -> Result<Self, DebError> {
+        // Creates an error for the field being parsed, whose name and line
+        // are filled in by `in_field()`
+        fn invalid(reason: &str) -> DebError {
+            DebError::InvalidControlField {
+                field: String::new(),
+                line: 0,
+                reason: reason.to_string(),
+            }
+        }
+
+        // Converts a yes/no field to bool
+        fn parse_bool(input: &str) -> Result<bool, DebError> {
+            match input {
+                "yes" => Ok(true),
+                "no" => Ok(false),
+                &_ => Err(invalid("expected yes or no")),
+            }
+        }
+
         // Converts comma-separated lists to Vec<String>
         fn split_to_vec(input: &str) -> Vec<String> {
             input
@@ -148,10 +237,18 @@ impl DebControl {
 
         let mut output = Self {
             name: String::new(),
-            version: String::new(),
+            source: String::new(),
+            source_version: None,
+            version: None,
+            section: String::new(),
             priority: DebPriority::Optional,
             architecture: DebArchitecture::All,
+            multi_arch: None,
             essential: false,
+            protected: false,
+            build_essential: false,
+            installed_size: None,
+            installed_size_set: false,
             depends: Vec::new(),
             pre_depends: Vec::new(),
             recommends: Vec::new(),
@@ -165,121 +262,145 @@ impl DebControl {
             description: String::new(),
             homepage: String::new(),
             built_using: Vec::new(),
+            tags: Vec::new(),
+            extra: DebParagraph::new(),
         };
 
-        let mut control_string = match String::from_utf8(control) {
+        let control_string = match String::from_utf8(control) {
             Ok(string) => string,
-            Err(e) => return Err(Error::new(ErrorKind::Other, e)),
+            Err(e) => {
+                let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+                return Err(DebError::InvalidSyntax {
+                    line: valid.iter().filter(|b| **b == b'\n').count() + 1,
+                    reason: "control file is not valid UTF-8".to_string(),
+                });
+            }
         };
+        let paragraph = DebParagraph::parse(&control_string)?;
 
-        // Splits control into Vec<&str> by line, and then splits
-        // each line into Vec<&str> by the key-value separating colon,
-        // ultimately resulting in Vec<Vec<&str>>
-        let iterator = control_string
-            .split('\n')
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|str| {
-                str.split(':')
-                    .collect::<Vec<&str>>()
-                    .into_iter()
-                    .map(|str| str.trim())
-                    .collect::<Vec<&str>>()
-            })
-            .collect::<Vec<Vec<&str>>>();
-
-        for line in iterator {
-            // This is to ensure that the trailing newline at a control file's
-            // end doesn't cause an error
-            if line.len() == 1 {
-                continue;
-            } else if line.len() != 2 {
-                return Err(Error::new(ErrorKind::Other, "control file is invalid"));
-            }
+        for (key, value) in paragraph.fields() {
+            // Multi-line values are only meaningful for the description, so
+            // folded relationship fields are joined back onto a single line
+            let value = value.as_str();
+            let line = value.replace('\n', " ");
             // Matches the key and writes the value to the appropriate field
-            match line[0] {
-                "Package" => {
-                    output.name = line[1].to_string();
-                }
-                "Version" => {
-                    output.version = line[1].to_string();
-                }
-                "Priority" => {
-                    output.priority = DebPriority::from(line[1])?;
-                }
-                "Architecture" => {
-                    output.architecture = DebArchitecture::from(line[1])?;
-                }
-                "Essential" => {
-                    output.essential = match line[1] {
-                        "yes" => true,
-                        "no" => false,
-                        &_ => {
-                            return Err(Error::new(ErrorKind::Other, "control file is invalid"));
+            let mut parse_field = || -> Result<(), DebError> {
+                match key.to_ascii_lowercase().as_str() {
+                    "package" => {
+                        output.name = line;
+                    }
+                    "version" => {
+                        output.version = Some(DebVersion::from(&line)?);
+                    }
+                    "priority" => {
+                        output.priority = DebPriority::from(&line)?;
+                    }
+                    "architecture" => {
+                        output.architecture = DebArchitecture::from(&line)?;
+                    }
+                    "source" => {
+                        // The source version is only present if it differs from
+                        // the binary package's, as in `name (version)`
+                        match line.split_once('(') {
+                            Some((name, version)) => {
+                                output.source = name.trim().to_string();
+                                output.source_version = match version.trim().strip_suffix(')') {
+                                    Some(version) => Some(DebVersion::from(version)?),
+                                    None => {
+                                        return Err(invalid("source version is missing `)`"));
+                                    }
+                                };
+                            }
+                            None => {
+                                output.source = line;
+                            }
                         }
                     }
-                }
-                "Depends" => {
-                    output.depends = split_to_vec(line[1]);
-                }
-                "Pre-Depends" => {
-                    output.pre_depends = split_to_vec(line[1]);
-                }
-                "Recommends" => {
-                    output.recommends = split_to_vec(line[1]);
-                }
-                "Suggests" => {
-                    output.suggests = split_to_vec(line[1]);
-                }
-                "Breaks" => {
-                    output.breaks = split_to_vec(line[1]);
-                }
-                "Conflicts" => {
-                    output.conflicts = split_to_vec(line[1]);
-                }
-                "Provides" => {
-                    output.provides = split_to_vec(line[1]);
-                }
-                "Replaces" => {
-                    output.replaces = split_to_vec(line[1]);
-                }
-                "Enhances" => {
-                    output.enhances = split_to_vec(line[1]);
-                }
-                "Maintainer" => {
-                    output.maintainer = line[1].to_string();
-                }
-                "Description" => {
-                    output.description = line[1].to_string();
-                }
-                "Homepage" => {
-                    output.homepage = line[1].to_string();
-                }
-                "Built-Using" => {
-                    // Pulls the version number out of the `name (= ver)` format
-                    // in Built-Using
-                    // god i hate regex syntax
-                    let ver_regex: Regex = Regex::new(r"\(= ([^()]*)\)$").unwrap();
-                    let mut built_using: Vec<[String; 2]> = Vec::new();
-                    let source = split_to_vec(line[1]);
-                    for entry in source {
-                        built_using.push([
-                            entry.split(' ').collect::<Vec<&str>>()[0].to_string(),
-                            match ver_regex.find(line[1]) {
-                                Some(mat) => mat.as_str().to_string(),
-                                None => {
-                                    return Err(Error::new(
-                                        ErrorKind::Other,
-                                        "control file is invalid",
-                                    ));
-                                }
-                            },
-                        ]);
+                    "section" => {
+                        output.section = line;
+                    }
+                    "multi-arch" => {
+                        output.multi_arch = Some(DebMultiArch::from(&line)?);
+                    }
+                    "essential" => {
+                        output.essential = parse_bool(&line)?;
+                    }
+                    "protected" => {
+                        output.protected = parse_bool(&line)?;
+                    }
+                    "build-essential" => {
+                        output.build_essential = parse_bool(&line)?;
+                    }
+                    "installed-size" => {
+                        output.installed_size = match line.parse::<u64>() {
+                            Ok(size) => Some(size),
+                            Err(_) => return Err(invalid("expected a number")),
+                        };
+                    }
+                    "tag" => {
+                        output.tags = split_to_vec(&line);
+                    }
+                    "depends" => {
+                        output.depends = DebRelation::from_list(&line)?;
+                    }
+                    "pre-depends" => {
+                        output.pre_depends = DebRelation::from_list(&line)?;
+                    }
+                    "recommends" => {
+                        output.recommends = DebRelation::from_list(&line)?;
+                    }
+                    "suggests" => {
+                        output.suggests = DebRelation::from_list(&line)?;
+                    }
+                    "breaks" => {
+                        output.breaks = DebRelation::from_list(&line)?;
+                    }
+                    "conflicts" => {
+                        output.conflicts = DebRelation::from_list(&line)?;
+                    }
+                    "provides" => {
+                        output.provides = DebRelation::from_list(&line)?;
+                    }
+                    "replaces" => {
+                        output.replaces = DebRelation::from_list(&line)?;
+                    }
+                    "enhances" => {
+                        output.enhances = DebRelation::from_list(&line)?;
+                    }
+                    "maintainer" => {
+                        output.maintainer = line;
+                    }
+                    "description" => {
+                        output.description = value.to_string();
+                    }
+                    "homepage" => {
+                        output.homepage = line;
+                    }
+                    "built-using" => {
+                        let built_using = DebRelation::from_list(&line)?;
+                        // Each entry names the exact version of a source package
+                        for relation in &built_using {
+                            match relation.alternatives()[..] {
+                                [ref dependency]
+                                    if matches!(
+                                        dependency.version(),
+                                        Some((DebVersionOperator::Equal, _))
+                                    ) => {}
+                                _ => return Err(invalid("expected `name (= version)`")),
+                            }
+                        }
+                        output.built_using = built_using;
+                    }
+                    // Fields this crate doesn't model are kept as-is so they
+                    // survive being read and written back out
+                    &_ => {
+                        output.extra.set(key, value);
                     }
                 }
-                &_ => {
-                    return Err(Error::new(ErrorKind::Other, "control file is invalid"));
-                }
+                Ok(())
+            };
+            if let Err(e) = parse_field() {
+                return Err(e.in_field(key, field_line(&control_string, key)));
             }
         }
 
@@ -287,6 +408,103 @@ impl DebControl {
     }
 }
 
+/// The result of checking a package's files against its `md5sums` member,
+/// returned by DebPackage's `verify()` method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebVerification {
+    mismatched: Vec<PathBuf>, // Files whose checksums don't match
+    missing: Vec<PathBuf>,    // Files listed in md5sums which aren't in the package
+    extra: Vec<PathBuf>,      // Files in the package which aren't listed in md5sums
+}
+
+impl DebVerification {
+    /// Returns whether every file matched its checksum, with no missing or
+    /// extra files.
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+
+    /// Returns the files whose contents don't match their checksums.
+    pub fn mismatched(&self) -> &Vec<PathBuf> {
+        &self.mismatched
+    }
+
+    /// Returns the files listed in md5sums which aren't in the package.
+    pub fn missing(&self) -> &Vec<PathBuf> {
+        &self.missing
+    }
+
+    /// Returns the files in the package which aren't listed in md5sums.
+    pub fn extra(&self) -> &Vec<PathBuf> {
+        &self.extra
+    }
+}
+
+/// An entry in a package's `conffiles` list.
+///
+/// Conffiles that are shipped in the package are usually marked with DebFile's
+/// `set_conffile()` method instead. This struct represents conffiles that aren't
+/// shipped, such as obsolete ones to be removed with the `remove-on-upgrade` flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebConffile {
+    path: PathBuf,           // The conffile's path on the user's system
+    remove_on_upgrade: bool, // Whether dpkg should remove the conffile on upgrade
+}
+
+impl DebConffile {
+    /// Creates a DebConffile for the file at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            remove_on_upgrade: false,
+        }
+    }
+
+    /// Sets whether dpkg should remove the conffile when the package is upgraded.
+    ///
+    /// Conffiles with this flag must not be shipped in the package.
+    pub fn set_remove_on_upgrade(mut self, remove_on_upgrade: bool) -> Self {
+        self.remove_on_upgrade = remove_on_upgrade;
+        self
+    }
+
+    /// Returns the conffile's path.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Returns whether dpkg will remove the conffile when the package is upgraded.
+    pub fn remove_on_upgrade(&self) -> bool {
+        self.remove_on_upgrade
+    }
+
+    // Parses the `conffiles` member of a control archive
+    fn parse_list(input: &str) -> Result<Vec<Self>, DebError> {
+        let mut output = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let mut conffile = Self::new(words.next().unwrap());
+            for flag in words {
+                match flag {
+                    "remove-on-upgrade" => conffile.remove_on_upgrade = true,
+                    _ => {
+                        return Err(DebError::InvalidMember {
+                            member: "conffiles".to_string(),
+                            line: index + 1,
+                            reason: format!("unknown flag {}", flag),
+                        })
+                    }
+                }
+            }
+            output.push(conffile);
+        }
+        Ok(output)
+    }
+}
+
 /// A high-level structure representing a Deb package.
 ///
 /// For binary package's, it may be helpful to read
@@ -297,16 +515,28 @@ impl DebControl {
 ///
 /// [1]: https://www.debian.org/doc/debian-policy/ch-controlfields.html#binary-package-control-files-debian-control
 /// [2]: https://www.debian.org/doc/debian-policy/ch-binary.html#maintainer-scripts
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DebPackage {
-    control: DebControl,         // Package's metadata
-    data: Vec<DebFile>,          // Package's contents
-    config: Option<DebFile>,     // Package's config script
-    preinst: Option<DebFile>,    // Package's preinstall script
-    postinst: Option<DebFile>,   // Package's postinstall script
-    prerm: Option<DebFile>,      // Package's preuninstall script
-    postrm: Option<DebFile>,     // Package's postuninstall script
-    compression: DebCompression, // Configures the package's compression standard
+    control: DebControl,                        // Package's metadata
+    data: Vec<DebFile>,                         // Package's contents
+    config: Option<DebFile>,                    // Package's config script
+    preinst: Option<DebFile>,                   // Package's preinstall script
+    postinst: Option<DebFile>,                  // Package's postinstall script
+    prerm: Option<DebFile>,                     // Package's preuninstall script
+    postrm: Option<DebFile>,                    // Package's postuninstall script
+    triggers: Vec<DebTrigger>,                  // Package's triggers
+    shlibs: Vec<DebShlib>,                      // Package's shared library dependencies
+    symbols: Vec<DebSymbols>,                   // Package's shared library symbols
+    templates: Vec<DebTemplate>,                // Package's debconf templates
+    control_files: Vec<DebFile>,                // Additional control archive members
+    md5sums: Option<Vec<(PathBuf, String)>>,    // Checksums read from the package's md5sums
+    compression: DebCompression, // Configures the data archive's compression standard
+    control_compression: DebCompression, // Configures the control archive's compression standard
+    compression_options: DebCompressionOptions, // Configures the level, threads, etc. of compression
+    reproducible: bool,                         // Whether builds are bit-for-bit reproducible
+    source_date_epoch: Option<u64>,             // Time reproducible builds clamp mtimes to
+    conffiles: Vec<DebConffile>,                // Conffiles which aren't marked on a file
+    etc_conffiles: bool,                        // Whether files under /etc are marked as conffiles
 }
 
 impl DebPackage {
@@ -315,10 +545,18 @@ impl DebPackage {
         Self {
             control: DebControl {
                 name: name.to_string(),
-                version: String::new(),
+                source: String::new(),
+                source_version: None,
+                version: None,
+                section: String::new(),
                 priority: DebPriority::Optional,
                 architecture: DebArchitecture::All,
+                multi_arch: None,
                 essential: false,
+                protected: false,
+                build_essential: false,
+                installed_size: None,
+                installed_size_set: false,
                 depends: Vec::new(),
                 pre_depends: Vec::new(),
                 recommends: Vec::new(),
@@ -332,6 +570,8 @@ impl DebPackage {
                 description: String::new(),
                 homepage: String::new(),
                 built_using: Vec::new(),
+                tags: Vec::new(),
+                extra: DebParagraph::new(),
             },
             data: Vec::new(),
             config: None,
@@ -339,15 +579,35 @@ impl DebPackage {
             postinst: None,
             prerm: None,
             postrm: None,
+            triggers: Vec::new(),
+            shlibs: Vec::new(),
+            symbols: Vec::new(),
+            templates: Vec::new(),
+            control_files: Vec::new(),
+            md5sums: None,
             compression: DebCompression::Zstd,
+            control_compression: DebCompression::Zstd,
+            compression_options: DebCompressionOptions::new(),
+            reproducible: false,
+            source_date_epoch: None,
+            conffiles: Vec::new(),
+            etc_conffiles: false,
         }
     }
 
     /// Reads a DebPackage from `input`.
-    pub fn from<R: Read>(mut input: R) -> std::io::Result<Self> {
+    pub fn from<R: Read>(mut input: R) -> Result<Self, DebError> {
         DebArchive::read(input)?.to_package()
     }
 
+    // Creates a DebPackage without any files from the contents of a control
+    // file, such as a stanza of a repository's Packages index
+    pub(crate) fn from_control(control: &str) -> Result<Self, DebError> {
+        let mut output = Self::new("");
+        output.control = DebControl::deserialize(control.as_bytes().to_vec())?;
+        Ok(output)
+    }
+
     /// Sets the package's name.
     pub fn set_name(mut self, name: &str) -> Self {
         self.control.name = name.to_string();
@@ -355,8 +615,34 @@ impl DebPackage {
     }
 
     /// Sets the package's version.
-    pub fn set_version(mut self, version: &str) -> Self {
-        self.control.version = version.to_string();
+    pub fn set_version(mut self, version: DebVersion) -> Self {
+        self.control.version = Some(version);
+        self
+    }
+
+    /// Sets the package's source package name.
+    pub fn set_source(mut self, source: &str) -> Self {
+        self.control.source = source.to_string();
+        self
+    }
+
+    /// Sets the package's source package version.
+    ///
+    /// This only needs to be set if it differs from the package's version.
+    pub fn set_source_version(mut self, version: DebVersion) -> Self {
+        self.control.source_version = Some(version);
+        self
+    }
+
+    /// Resets the package's source package version.
+    pub fn no_source_version(mut self) -> Self {
+        self.control.source_version = None;
+        self
+    }
+
+    /// Sets the package's section, such as `utils` or `non-free/libs`.
+    pub fn set_section(mut self, section: &str) -> Self {
+        self.control.section = section.to_string();
         self
     }
 
@@ -372,23 +658,62 @@ impl DebPackage {
         self
     }
 
+    /// Sets the package's Multi-Arch value.
+    pub fn set_multi_arch(mut self, multi_arch: DebMultiArch) -> Self {
+        self.control.multi_arch = Some(multi_arch);
+        self
+    }
+
+    /// Resets the package's Multi-Arch value.
+    pub fn no_multi_arch(mut self) -> Self {
+        self.control.multi_arch = None;
+        self
+    }
+
     /// Sets whether the package is essential.
     pub fn set_essential(mut self, essential: bool) -> Self {
         self.control.essential = essential;
         self
     }
 
-    /// Adds a single dependency from &str.
-    pub fn with_depend(mut self, depend: &str) -> Self {
-        self.control.depends.push(depend.to_string());
+    /// Sets whether the package is protected.
+    pub fn set_protected(mut self, protected: bool) -> Self {
+        self.control.protected = protected;
         self
     }
 
-    /// Adds a number of dependencies from Vec<&str>.
-    pub fn with_depends(mut self, depends: Vec<&str>) -> Self {
-        self.control
-            .depends
-            .append(&mut depends.iter().map(|str| str.to_string()).collect());
+    /// Sets whether the package is build-essential.
+    pub fn set_build_essential(mut self, build_essential: bool) -> Self {
+        self.control.build_essential = build_essential;
+        self
+    }
+
+    /// Sets the package's installed size in kibibytes.
+    ///
+    /// If this isn't set, it will be calculated from the package's files when
+    /// the package is built, replacing any size read from an existing package.
+    pub fn set_installed_size(mut self, size: u64) -> Self {
+        self.control.installed_size = Some(size);
+        self.control.installed_size_set = true;
+        self
+    }
+
+    /// Resets the package's installed size, so it's calculated when the package is built.
+    pub fn no_installed_size(mut self) -> Self {
+        self.control.installed_size = None;
+        self.control.installed_size_set = false;
+        self
+    }
+
+    /// Adds a single dependency.
+    pub fn with_depend(mut self, depend: DebRelation) -> Self {
+        self.control.depends.push(depend);
+        self
+    }
+
+    /// Adds a number of dependencies.
+    pub fn with_depends(mut self, depends: Vec<DebRelation>) -> Self {
+        self.control.depends.extend(depends);
         self
     }
 
@@ -398,17 +723,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single pre-dependency from &str.
-    pub fn with_pre_depend(mut self, depend: &str) -> Self {
-        self.control.pre_depends.push(depend.to_string());
+    /// Adds a single pre-dependency.
+    pub fn with_pre_depend(mut self, depend: DebRelation) -> Self {
+        self.control.pre_depends.push(depend);
         self
     }
 
-    /// Adds a number of pre-dependencies from Vec<&str>.
-    pub fn with_pre_depends(mut self, depends: Vec<&str>) -> Self {
-        self.control
-            .pre_depends
-            .append(&mut depends.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of pre-dependencies.
+    pub fn with_pre_depends(mut self, depends: Vec<DebRelation>) -> Self {
+        self.control.pre_depends.extend(depends);
         self
     }
 
@@ -418,17 +741,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single recommend from &str.
-    pub fn with_recommend(mut self, recommend: &str) -> Self {
-        self.control.recommends.push(recommend.to_string());
+    /// Adds a single recommend.
+    pub fn with_recommend(mut self, recommend: DebRelation) -> Self {
+        self.control.recommends.push(recommend);
         self
     }
 
-    /// Adds a number of recommends from Vec<&str>.
-    pub fn with_recommends(mut self, recommends: Vec<&str>) -> Self {
-        self.control
-            .recommends
-            .append(&mut recommends.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of recommends.
+    pub fn with_recommends(mut self, recommends: Vec<DebRelation>) -> Self {
+        self.control.recommends.extend(recommends);
         self
     }
 
@@ -438,17 +759,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single suggest from &str.
-    pub fn with_suggest(mut self, suggest: &str) -> Self {
-        self.control.suggests.push(suggest.to_string());
+    /// Adds a single suggest.
+    pub fn with_suggest(mut self, suggest: DebRelation) -> Self {
+        self.control.suggests.push(suggest);
         self
     }
 
-    /// Adds a number of suggests from Vec<&str>.
-    pub fn with_suggests(mut self, suggests: Vec<&str>) -> Self {
-        self.control
-            .suggests
-            .append(&mut suggests.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of suggests.
+    pub fn with_suggests(mut self, suggests: Vec<DebRelation>) -> Self {
+        self.control.suggests.extend(suggests);
         self
     }
 
@@ -458,17 +777,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single break from &str.
-    pub fn with_break(mut self, conflict: &str) -> Self {
-        self.control.breaks.push(conflict.to_string());
+    /// Adds a single break.
+    pub fn with_break(mut self, conflict: DebRelation) -> Self {
+        self.control.breaks.push(conflict);
         self
     }
 
-    /// Adds a number of breaks from Vec<&str>.
-    pub fn with_breaks(mut self, conflicts: Vec<&str>) -> Self {
-        self.control
-            .breaks
-            .append(&mut conflicts.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of breaks.
+    pub fn with_breaks(mut self, conflicts: Vec<DebRelation>) -> Self {
+        self.control.breaks.extend(conflicts);
         self
     }
 
@@ -478,17 +795,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single conflict from &str.
-    pub fn with_conflict(mut self, conflict: &str) -> Self {
-        self.control.conflicts.push(conflict.to_string());
+    /// Adds a single conflict.
+    pub fn with_conflict(mut self, conflict: DebRelation) -> Self {
+        self.control.conflicts.push(conflict);
         self
     }
 
-    /// Adds a number of conflicts from Vec<&str>.
-    pub fn with_conflicts(mut self, conflicts: Vec<&str>) -> Self {
-        self.control
-            .conflicts
-            .append(&mut conflicts.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of conflicts.
+    pub fn with_conflicts(mut self, conflicts: Vec<DebRelation>) -> Self {
+        self.control.conflicts.extend(conflicts);
         self
     }
 
@@ -498,17 +813,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single provide from &str.
-    pub fn with_provide(mut self, provide: &str) -> Self {
-        self.control.provides.push(provide.to_string());
+    /// Adds a single provide.
+    pub fn with_provide(mut self, provide: DebRelation) -> Self {
+        self.control.provides.push(provide);
         self
     }
 
-    /// Adds a number of provides from Vec<&str>.
-    pub fn with_provides(mut self, provides: Vec<&str>) -> Self {
-        self.control
-            .provides
-            .append(&mut provides.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of provides.
+    pub fn with_provides(mut self, provides: Vec<DebRelation>) -> Self {
+        self.control.provides.extend(provides);
         self
     }
 
@@ -518,17 +831,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single replace from &str.
-    pub fn with_replace(mut self, replace: &str) -> Self {
-        self.control.replaces.push(replace.to_string());
+    /// Adds a single replace.
+    pub fn with_replace(mut self, replace: DebRelation) -> Self {
+        self.control.replaces.push(replace);
         self
     }
 
-    /// Adds a number of replaces from Vec<&str>.
-    pub fn with_replaces(mut self, replaces: Vec<&str>) -> Self {
-        self.control
-            .replaces
-            .append(&mut replaces.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of replaces.
+    pub fn with_replaces(mut self, replaces: Vec<DebRelation>) -> Self {
+        self.control.replaces.extend(replaces);
         self
     }
 
@@ -538,17 +849,15 @@ impl DebPackage {
         self
     }
 
-    /// Adds a single enhance from &str.
-    pub fn with_enhance(mut self, enhance: &str) -> Self {
-        self.control.enhances.push(enhance.to_string());
+    /// Adds a single enhance.
+    pub fn with_enhance(mut self, enhance: DebRelation) -> Self {
+        self.control.enhances.push(enhance);
         self
     }
 
-    /// Adds a number of enhances from Vec<&str>.
-    pub fn with_enhances(mut self, enhances: Vec<&str>) -> Self {
-        self.control
-            .enhances
-            .append(&mut enhances.iter().map(|str| str.to_string()).collect());
+    /// Adds a number of enhances.
+    pub fn with_enhances(mut self, enhances: Vec<DebRelation>) -> Self {
+        self.control.enhances.extend(enhances);
         self
     }
 
@@ -566,7 +875,7 @@ impl DebPackage {
 
     /// Sets the package's description.
     pub fn set_description(mut self, description: &str) -> Self {
-        self.control.description = description.to_string();
+        self.control.description = fold_value(description);
         self
     }
 
@@ -576,11 +885,11 @@ impl DebPackage {
         self
     }
 
-    /// Adds a "built using" package.
-    pub fn with_built_using(mut self, using: &str, version: &str) -> Self {
-        self.control
-            .built_using
-            .push([using.to_string(), version.to_string()]);
+    /// Adds a "built using" package, the source package `using` at exactly `version`.
+    pub fn with_built_using(mut self, using: &str, version: DebVersion) -> Self {
+        self.control.built_using.push(DebRelation::new(
+            DebDependency::new(using).set_version(DebVersionOperator::Equal, version),
+        ));
         self
     }
 
@@ -590,9 +899,65 @@ impl DebPackage {
         self
     }
 
+    /// Adds a single [debtag](https://wiki.debian.org/Debtags), such as `role::program`.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.control.tags.push(tag.to_string());
+        self
+    }
+
+    /// Adds a number of debtags from Vec<&str>.
+    pub fn with_tags(mut self, tags: Vec<&str>) -> Self {
+        self.control
+            .tags
+            .append(&mut tags.iter().map(|str| str.to_string()).collect());
+        self
+    }
+
+    /// Resets debtags.
+    pub fn no_tags(mut self) -> Self {
+        self.control.tags = Vec::new();
+        self
+    }
+
+    /// Adds an additional control field, such as `X-Build-Commit`.
+    ///
+    /// If a field named `name` was already added, its value is replaced. Fields
+    /// this crate already supports, such as `Package` or `Depends`, should be set
+    /// with their own methods, as those take precedence when the package is built.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use deb_rust::binary::DebPackage;
+    ///
+    /// let mut package = DebPackage::new("example")
+    ///     .with_field("X-Build-Commit", "3f2a9c1")
+    ///     .with_field("X-Team", "infrastructure");
+    /// ```
+    pub fn with_field(mut self, name: &str, value: &str) -> Self {
+        self.control.extra.set(name, value);
+        self
+    }
+
+    /// Removes an additional control field.
+    pub fn without_field(mut self, name: &str) -> Self {
+        self.control.extra.remove(name);
+        self
+    }
+
+    /// Resets additional control fields.
+    pub fn no_fields(mut self) -> Self {
+        self.control.extra = DebParagraph::new();
+        self
+    }
+
     /// Adds a file to the package.
-    pub fn with_file(mut self, file: DebFile) -> Self {
+    pub fn with_file(mut self, mut file: DebFile) -> Self {
+        if self.etc_conffiles && file.path().starts_with("/etc") {
+            file = file.set_conffile(true);
+        }
         self.data.push(file);
+        self.mark_conffiles();
         self
     }
 
@@ -603,6 +968,9 @@ impl DebPackage {
     /// you can add those files as `/usr/bin/foo` and `/usr/bin/bar` with
     /// `with_dir("test", "/usr/bin")?;`
     ///
+    /// Directory entries are added for `to` and each sub-directory, and symlinks
+    /// are added as symlinks rather than followed.
+    ///
     /// This function isn't available when compiling on Windows, as it's utility
     /// relies on being able to read the modes of the directory's children,
     /// which is a feature Windows lacks.
@@ -620,7 +988,7 @@ impl DebPackage {
     ///     .with_dir("test", "/usr/bin").unwrap();
     /// ```
     #[cfg(unix)]
-    pub fn with_dir<P>(mut self, from: P, to: P) -> std::io::Result<Self>
+    pub fn with_dir<P>(mut self, from: P, to: P) -> Result<Self, DebError>
     where
         P: AsRef<Path>,
     {
@@ -629,19 +997,85 @@ impl DebPackage {
         path_from.push(from);
         path_to.push(to);
         for file_result in walkdir::WalkDir::new(&path_from) {
-            let file = file_result?;
-            if file.path().is_file() {
-                // Cutting the `from` directory out of the path
-                let mut components = file.path().components();
-                for _i in path_from.components() {
-                    components.next();
-                }
-                self = self.with_file(DebFile::from_path(file.path(), path_to.join(components))?);
+            let file = file_result.map_err(std::io::Error::from)?;
+            // Cutting the `from` directory out of the path
+            let mut components = file.path().components();
+            for _i in path_from.components() {
+                components.next();
             }
+            self = self.with_file(DebFile::from_path(file.path(), path_to.join(components))?);
         }
         Ok(self)
     }
 
+    /// Adds `path` to the package's conffiles.
+    ///
+    /// If the package has a file at `path`, or one is added later, that file is
+    /// marked as a conffile. Otherwise, `path` is listed as a conffile that isn't
+    /// shipped in the package.
+    pub fn with_conffile<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.conffiles.push(DebConffile::new(path));
+        self.mark_conffiles();
+        self
+    }
+
+    /// Adds `path` to the package's conffiles with the `remove-on-upgrade` flag,
+    /// so dpkg removes it when upgrading from an older version of the package.
+    pub fn with_removed_conffile<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.conffiles
+            .push(DebConffile::new(path).set_remove_on_upgrade(true));
+        self
+    }
+
+    /// Resets the package's conffiles, including those marked on its files.
+    pub fn no_conffiles(mut self) -> Self {
+        self.conffiles = Vec::new();
+        self.data = self
+            .data
+            .into_iter()
+            .map(|file| file.set_conffile(false))
+            .collect();
+        self
+    }
+
+    /// Sets whether every file under `/etc` is marked as a conffile, like
+    /// debhelper does.
+    ///
+    /// This applies to files already in the package, and files added later.
+    pub fn set_etc_conffiles(mut self, etc_conffiles: bool) -> Self {
+        self.etc_conffiles = etc_conffiles;
+        if etc_conffiles {
+            self.data = self
+                .data
+                .into_iter()
+                .map(|file| match file.path().starts_with("/etc") {
+                    true => file.set_conffile(true),
+                    false => file,
+                })
+                .collect();
+        }
+        self
+    }
+
+    // Moves conffiles listed without a flag onto the files they refer to, so
+    // a conffile is always represented the same way however it was added
+    fn mark_conffiles(&mut self) {
+        let data = &mut self.data;
+        self.conffiles.retain(|conffile| {
+            if conffile.remove_on_upgrade {
+                return true;
+            }
+            match data.iter().position(|file| file.path() == conffile.path()) {
+                Some(index) => {
+                    let file = data.remove(index).set_conffile(true);
+                    data.insert(index, file);
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+
     /// Removes all file's from the package.
     pub fn clear_files(mut self) -> Self {
         self.data = Vec::new();
@@ -738,79 +1172,249 @@ impl DebPackage {
         self
     }
 
-    /// Sets the package's compression standard.
-    pub fn set_compression(mut self, compression: DebCompression) -> Self {
-        self.compression = compression;
+    /// Adds a trigger to the package's triggers file.
+    pub fn with_trigger(mut self, trigger: DebTrigger) -> Self {
+        self.triggers.push(trigger);
         self
     }
 
-    /// Returns the package's name.
-    pub fn name(&self) -> &str {
-        &self.control.name
+    /// Resets the package's triggers.
+    pub fn no_triggers(mut self) -> Self {
+        self.triggers = Vec::new();
+        self
     }
 
-    /// Returns the package's version.
-    pub fn version(&self) -> &str {
-        &self.control.version
+    /// Adds an entry to the package's shlibs file.
+    pub fn with_shlib(mut self, shlib: DebShlib) -> Self {
+        self.shlibs.push(shlib);
+        self
     }
 
-    /// Returns the package's priority.
-    pub fn priority(&self) -> &DebPriority {
-        &self.control.priority
+    /// Resets the package's shlibs.
+    pub fn no_shlibs(mut self) -> Self {
+        self.shlibs = Vec::new();
+        self
     }
 
-    /// Returns the package's architecture.
-    pub fn architecture(&self) -> &DebArchitecture {
-        &self.control.architecture
+    /// Adds a library's symbols to the package's symbols file.
+    pub fn with_symbols(mut self, symbols: DebSymbols) -> Self {
+        self.symbols.push(symbols);
+        self
     }
 
-    /// Returns whether the package is essential.
-    pub fn essential(&self) -> bool {
-        self.control.essential
+    /// Resets the package's symbols.
+    pub fn no_symbols(mut self) -> Self {
+        self.symbols = Vec::new();
+        self
     }
 
-    /// Returns the package's depends.
-    pub fn depends(&self) -> &Vec<String> {
-        &self.control.depends
+    /// Adds a debconf template to the package's templates file.
+    pub fn with_template(mut self, template: DebTemplate) -> Self {
+        self.templates.push(template);
+        self
     }
 
-    /// Returns the package's pre-depends.
-    pub fn pre_depends(&self) -> &Vec<String> {
-        &self.control.pre_depends
+    /// Resets the package's debconf templates.
+    pub fn no_templates(mut self) -> Self {
+        self.templates = Vec::new();
+        self
     }
 
-    /// Returns the package's recommends.
-    pub fn recommends(&self) -> &Vec<String> {
-        &self.control.recommends
+    /// Adds a file to the package's control archive.
+    ///
+    /// This is meant for control members this crate doesn't model. Members such
+    /// as `control`, `md5sums` and the maintainer scripts are generated from the
+    /// package, so `file` mustn't use their names.
+    pub fn with_control_file(mut self, file: DebFile) -> Self {
+        self.control_files.push(file);
+        self
     }
 
-    /// Returns the package's suggests.
-    pub fn suggests(&self) -> &Vec<String> {
-        &self.control.suggests
+    /// Resets the package's additional control archive members.
+    pub fn no_control_files(mut self) -> Self {
+        self.control_files = Vec::new();
+        self
     }
 
-    /// Returns the package's breaks.
-    pub fn breaks(&self) -> &Vec<String> {
-        &self.control.breaks
+    /// Sets the package's compression standard for both the control and data archives.
+    ///
+    /// As dpkg doesn't accept bzip2 or lzma compressed control archives, those
+    /// only apply to the data archive, leaving the control archive's compression
+    /// as it was.
+    pub fn set_compression(mut self, compression: DebCompression) -> Self {
+        self.compression = compression;
+        if compression.supports_control() {
+            self.control_compression = compression;
+        }
+        self
     }
 
-    /// Returns the package's conflicts.
-    pub fn conflicts(&self) -> &Vec<String> {
-        &self.control.conflicts
+    /// Sets the compression standard of the package's data archive only.
+    pub fn set_data_compression(mut self, compression: DebCompression) -> Self {
+        self.compression = compression;
+        self
     }
 
-    /// Returns the package's provides.
-    pub fn provides(&self) -> &Vec<String> {
-        &self.control.provides
+    /// Sets the compression standard of the package's control archive only.
+    ///
+    /// dpkg only accepts uncompressed, gzip, xz and zstd control archives, so
+    /// building a package with any other compression returns an error.
+    pub fn set_control_compression(mut self, compression: DebCompression) -> Self {
+        self.control_compression = compression;
+        self
     }
 
-    /// Returns the package's replaces.
-    pub fn replaces(&self) -> &Vec<String> {
-        &self.control.replaces
+    /// Sets the options used to compress the package's archives, such as the
+    /// compression level and number of threads.
+    pub fn set_compression_options(mut self, options: DebCompressionOptions) -> Self {
+        self.compression_options = options;
+        self
+    }
+
+    /// Sets whether the package is built reproducibly, so identical packages
+    /// always build to identical .deb files.
+    ///
+    /// Reproducible builds clamp every modification time to the source date
+    /// epoch, which is taken from `set_source_date_epoch()`, or the
+    /// `SOURCE_DATE_EPOCH` environment variable when the package is built, or
+    /// else 0. Files are also sorted by path, with hardlinks last, and owned by
+    /// root:root, as with `dpkg-deb --root-owner-group`. Packages whose files
+    /// need other owners should set them in a maintainer script instead.
+    ///
+    /// Read the [Reproducible Builds documentation][1] for more details.
+    ///
+    /// [1]: https://reproducible-builds.org/docs/source-date-epoch/
+    pub fn set_reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// Sets the time reproducible builds clamp modification times to, in
+    /// seconds since the Unix epoch, overriding `SOURCE_DATE_EPOCH`.
+    pub fn set_source_date_epoch(mut self, epoch: u64) -> Self {
+        self.source_date_epoch = Some(epoch);
+        self
+    }
+
+    /// Resets the source date epoch, so `SOURCE_DATE_EPOCH` is used.
+    pub fn no_source_date_epoch(mut self) -> Self {
+        self.source_date_epoch = None;
+        self
+    }
+
+    /// Returns whether the package is built reproducibly.
+    pub fn reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// Returns the source date epoch set with `set_source_date_epoch()`, if any.
+    pub fn source_date_epoch(&self) -> Option<u64> {
+        self.source_date_epoch
+    }
+
+    /// Returns the package's name.
+    pub fn name(&self) -> &str {
+        &self.control.name
+    }
+
+    /// Returns the package's version, if it has been set.
+    pub fn version(&self) -> Option<&DebVersion> {
+        self.control.version.as_ref()
+    }
+
+    /// Returns the package's source package name.
+    pub fn source(&self) -> &str {
+        &self.control.source
+    }
+
+    /// Returns the package's source package version, if it differs from the package's version.
+    pub fn source_version(&self) -> Option<&DebVersion> {
+        self.control.source_version.as_ref()
+    }
+
+    /// Returns the package's section.
+    pub fn section(&self) -> &str {
+        &self.control.section
+    }
+
+    /// Returns the package's priority.
+    pub fn priority(&self) -> &DebPriority {
+        &self.control.priority
+    }
+
+    /// Returns the package's architecture.
+    pub fn architecture(&self) -> &DebArchitecture {
+        &self.control.architecture
+    }
+
+    /// Returns the package's Multi-Arch value.
+    pub fn multi_arch(&self) -> Option<&DebMultiArch> {
+        self.control.multi_arch.as_ref()
+    }
+
+    /// Returns whether the package is essential.
+    pub fn essential(&self) -> bool {
+        self.control.essential
+    }
+
+    /// Returns whether the package is protected.
+    pub fn protected(&self) -> bool {
+        self.control.protected
+    }
+
+    /// Returns whether the package is build-essential.
+    pub fn build_essential(&self) -> bool {
+        self.control.build_essential
+    }
+
+    /// Returns the package's installed size in kibibytes, if it has been set or
+    /// read from an existing package.
+    pub fn installed_size(&self) -> Option<u64> {
+        self.control.installed_size
+    }
+
+    /// Returns the package's depends.
+    pub fn depends(&self) -> &Vec<DebRelation> {
+        &self.control.depends
+    }
+
+    /// Returns the package's pre-depends.
+    pub fn pre_depends(&self) -> &Vec<DebRelation> {
+        &self.control.pre_depends
+    }
+
+    /// Returns the package's recommends.
+    pub fn recommends(&self) -> &Vec<DebRelation> {
+        &self.control.recommends
+    }
+
+    /// Returns the package's suggests.
+    pub fn suggests(&self) -> &Vec<DebRelation> {
+        &self.control.suggests
+    }
+
+    /// Returns the package's breaks.
+    pub fn breaks(&self) -> &Vec<DebRelation> {
+        &self.control.breaks
+    }
+
+    /// Returns the package's conflicts.
+    pub fn conflicts(&self) -> &Vec<DebRelation> {
+        &self.control.conflicts
+    }
+
+    /// Returns the package's provides.
+    pub fn provides(&self) -> &Vec<DebRelation> {
+        &self.control.provides
+    }
+
+    /// Returns the package's replaces.
+    pub fn replaces(&self) -> &Vec<DebRelation> {
+        &self.control.replaces
     }
 
     /// Returns the package's enhances.
-    pub fn enhances(&self) -> &Vec<String> {
+    pub fn enhances(&self) -> &Vec<DebRelation> {
         &self.control.enhances
     }
 
@@ -830,10 +1434,27 @@ impl DebPackage {
     }
 
     /// Returns the packages this package was built with.
-    pub fn built_using(&self) -> &Vec<[String; 2]> {
+    pub fn built_using(&self) -> &Vec<DebRelation> {
         &self.control.built_using
     }
 
+    /// Returns the package's debtags.
+    pub fn tags(&self) -> &Vec<String> {
+        &self.control.tags
+    }
+
+    /// Returns the value of additional control field `name`, if it exists.
+    ///
+    /// Field names are compared case-insensitively.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.control.extra.get(name)
+    }
+
+    /// Returns the package's additional control fields in order.
+    pub fn fields(&self) -> &Vec<(String, String)> {
+        self.control.extra.fields()
+    }
+
     /// Returns a vector of the packages files.
     pub fn files(&self) -> &Vec<DebFile> {
         &self.data
@@ -842,7 +1463,7 @@ impl DebPackage {
     /// Returns the package's config script.
     pub fn config(&self) -> Option<&Vec<u8>> {
         match &self.config {
-            Some(file) => Some(file.contents()),
+            Some(file) => file.contents(),
             None => None,
         }
     }
@@ -850,7 +1471,7 @@ impl DebPackage {
     /// Returns the package's preinst script.
     pub fn preinst(&self) -> Option<&Vec<u8>> {
         match &self.preinst {
-            Some(file) => Some(file.contents()),
+            Some(file) => file.contents(),
             None => None,
         }
     }
@@ -858,7 +1479,7 @@ impl DebPackage {
     /// Returns the package's postinst script.
     pub fn postinst(&self) -> Option<&Vec<u8>> {
         match &self.postinst {
-            Some(file) => Some(file.contents()),
+            Some(file) => file.contents(),
             None => None,
         }
     }
@@ -866,7 +1487,7 @@ impl DebPackage {
     /// Returns the package's prerm script.
     pub fn prerm(&self) -> Option<&Vec<u8>> {
         match &self.prerm {
-            Some(file) => Some(file.contents()),
+            Some(file) => file.contents(),
             None => None,
         }
     }
@@ -874,105 +1495,658 @@ impl DebPackage {
     /// Returns the package's postrm script.
     pub fn postrm(&self) -> Option<&Vec<u8>> {
         match &self.postrm {
-            Some(file) => Some(file.contents()),
+            Some(file) => file.contents(),
             None => None,
         }
     }
 
-    /// Returns the package's compression standard.
+    /// Returns the package's triggers.
+    pub fn triggers(&self) -> &Vec<DebTrigger> {
+        &self.triggers
+    }
+
+    /// Returns the package's shlibs entries.
+    pub fn shlibs(&self) -> &Vec<DebShlib> {
+        &self.shlibs
+    }
+
+    /// Returns the package's symbols, grouped by library.
+    pub fn symbols(&self) -> &Vec<DebSymbols> {
+        &self.symbols
+    }
+
+    /// Returns the package's debconf templates.
+    pub fn templates(&self) -> &Vec<DebTemplate> {
+        &self.templates
+    }
+
+    /// Returns the package's additional control archive members.
+    pub fn control_files(&self) -> &Vec<DebFile> {
+        &self.control_files
+    }
+
+    /// Returns the checksums listed in the package's `md5sums` member, as paths
+    /// paired with MD5 checksums.
+    ///
+    /// This is only set for packages which have been read, as the checksums of
+    /// packages being built are calculated by `build()`.
+    pub fn md5sums(&self) -> Option<&Vec<(PathBuf, String)>> {
+        self.md5sums.as_ref()
+    }
+
+    /// Checks the package's files against the checksums in its `md5sums` member.
+    ///
+    /// Only regular files are checked, as md5sums doesn't list directories or links.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the package has no md5sums, or if
+    /// any file can't be read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use deb_rust::binary::DebPackage;
+    ///
+    /// let package = DebPackage::from(File::open("example.deb")?)?;
+    /// let verification = package.verify()?;
+    /// if !verification.is_ok() {
+    ///     println!("corrupted files: {:?}", verification.mismatched());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn verify(&self) -> Result<DebVerification, DebError> {
+        let md5sums = match &self.md5sums {
+            Some(md5sums) => md5sums,
+            None => return Err(DebError::MissingMd5sums),
+        };
+        let mut output = DebVerification {
+            mismatched: Vec::new(),
+            missing: Vec::new(),
+            extra: Vec::new(),
+        };
+
+        for file in &self.data {
+            if *file.kind() != DebFileKind::File {
+                continue;
+            }
+            match md5sums.iter().find(|(path, _)| path == file.path()) {
+                Some((_, md5sum)) => {
+                    let mut context = md5::Context::new();
+                    std::io::copy(&mut file.open()?, &mut context)?;
+                    if format!("{:x}", context.compute()) != md5sum.to_lowercase() {
+                        output.mismatched.push(file.path().clone());
+                    }
+                }
+                None => output.extra.push(file.path().clone()),
+            }
+        }
+        for (path, _) in md5sums {
+            if !self.data.iter().any(|file| file.path() == path) {
+                output.missing.push(path.clone());
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Returns the package's conffiles, including files marked as conffiles.
+    pub fn conffiles(&self) -> Vec<DebConffile> {
+        let mut output: Vec<DebConffile> = self
+            .data
+            .iter()
+            .filter(|file| file.conffile() && *file.kind() == DebFileKind::File)
+            .map(|file| DebConffile::new(file.path()))
+            .collect();
+        output.extend(self.conffiles.iter().cloned());
+        output
+    }
+
+    /// Returns the compression standard of the package's data archive.
     pub fn compression(&self) -> &DebCompression {
         &self.compression
     }
 
+    /// Returns the compression standard of the package's control archive.
+    pub fn control_compression(&self) -> &DebCompression {
+        &self.control_compression
+    }
+
+    /// Returns the package's compression options.
+    pub fn compression_options(&self) -> &DebCompressionOptions {
+        &self.compression_options
+    }
+
+    /// Returns the package's control file as a deb822 paragraph.
+    ///
+    /// Installed-Size is only included if it's known, such as when the package
+    /// was read from a .deb file.
+    pub fn control_paragraph(&self) -> DebParagraph {
+        self.control.to_paragraph()
+    }
+
     /// Builds the package into a DebArchive struct.
-    pub fn build(&self) -> std::io::Result<DebArchive> {
+    ///
+    /// The compressed archives are held in memory. To write a package directly
+    /// to a file without doing so, use `build_to()`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any file can't be read, or if the
+    /// control archive's compression isn't supported by dpkg.
+    pub fn build(&self) -> Result<DebArchive, DebError> {
+        self.check_control_compression()?;
         let mut output = DebArchive {
             control: Vec::new(),
             data: Vec::new(),
-            compression: match self.compression {
-                DebCompression::Xz => DebCompression::Xz,
-                DebCompression::Zstd => DebCompression::Zstd,
-            },
+            control_compression: self.control_compression,
+            data_compression: self.compression,
+            format_version: b"2.0\n".to_vec(),
+            extra_members: Vec::new(),
+            extra_before_control: 0,
+            extra_before_data: 0,
+            limits: DebLimits::new(),
         };
 
-        // Creating tar archives
+        let sizes = self.file_sizes_and_hashes()?;
+        compress(
+            self.control_compression,
+            &self.compression_options,
+            self.control_tar(&sizes)?.as_slice(),
+            &mut output.control,
+        )?;
+        output.data = self.write_data_tar(&sizes, Vec::new())?;
+
+        Ok(output)
+    }
+
+    /// Builds the package and writes it to `output` as a .deb file.
+    ///
+    /// Unlike `build()`, file contents are streamed from their source through the
+    /// compressor straight into `output`, so memory usage doesn't grow with the
+    /// size of the package's files. Every file is read twice: once to calculate
+    /// its size and checksum, and once to write it to the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any file can't be read, if a file
+    /// changes size between the two times it's read, or if the control archive's
+    /// compression isn't supported by dpkg.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::fs::File;
+    /// use deb_rust::DebFile;
+    /// use deb_rust::binary::DebPackage;
+    ///
+    /// let package = DebPackage::new("example")
+    ///     .with_file(DebFile::from_path("target/release/example", "/usr/bin/example")?);
+    /// package.build_to(File::create("example.deb")?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_to<W: Write + Seek>(&self, mut output: W) -> Result<(), DebError> {
+        self.check_control_compression()?;
+        let sizes = self.file_sizes_and_hashes()?;
+        let mut control = Vec::new();
+        compress(
+            self.control_compression,
+            &self.compression_options,
+            self.control_tar(&sizes)?.as_slice(),
+            &mut control,
+        )?;
+
+        output.write_all(AR_MAGIC)?;
+        write_ar_member(&mut output, "debian-binary", b"2.0\n")?;
+        write_ar_member(
+            &mut output,
+            &format!("control.tar{}", self.control_compression.extension()),
+            &control,
+        )?;
+
+        // The data archive's size isn't known until it's been compressed, so
+        // its header is written with a placeholder size and patched afterwards
+        let data_name = format!("data.tar{}", self.compression.extension());
+        let header_start = output.stream_position()?;
+        write_ar_header(&mut output, &data_name, 0)?;
+        let data_start = output.stream_position()?;
+        self.write_data_tar(&sizes, &mut output)?;
+        let data_end = output.stream_position()?;
+
+        output.seek(SeekFrom::Start(header_start))?;
+        write_ar_header(&mut output, &data_name, data_end - data_start)?;
+        output.seek(SeekFrom::Start(data_end))?;
+        if (data_end - data_start) % 2 == 1 {
+            output.write_all(b"\n")?;
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    // Returns the time modification times are clamped to, if the package is
+    // built reproducibly
+    fn build_epoch(&self) -> Result<Option<u64>, DebError> {
+        if !self.reproducible {
+            return Ok(None);
+        }
+        if let Some(epoch) = self.source_date_epoch {
+            return Ok(Some(epoch));
+        }
+        match std::env::var("SOURCE_DATE_EPOCH") {
+            Ok(epoch) => match epoch.trim().parse::<u64>() {
+                Ok(epoch) => Ok(Some(epoch)),
+                Err(_) => Err(DebError::InvalidSourceDateEpoch(epoch)),
+            },
+            Err(_) => Ok(Some(0)),
+        }
+    }
+
+    // Returns the package's files in the order they're written. Reproducible
+    // builds sort them by path, with hardlinks last so they always come after
+    // the files they link to.
+    fn ordered_files(&self) -> Vec<&DebFile> {
+        let mut output: Vec<&DebFile> = self.data.iter().collect();
+        if self.reproducible {
+            output.sort_by(|a, b| {
+                let a_link = matches!(a.kind(), DebFileKind::Hardlink(_));
+                let b_link = matches!(b.kind(), DebFileKind::Hardlink(_));
+                a_link.cmp(&b_link).then_with(|| a.path().cmp(b.path()))
+            });
+        }
+        output
+    }
+
+    // Reads every file once, returning each file's size and md5 checksum
+    fn file_sizes_and_hashes(&self) -> Result<Vec<(u64, String)>, DebError> {
+        let mut output = Vec::with_capacity(self.data.len());
+        for file in self.ordered_files() {
+            if *file.kind() != DebFileKind::File {
+                output.push((0, String::new()));
+                continue;
+            }
+            let mut context = md5::Context::new();
+            let size = std::io::copy(&mut file.open()?, &mut context)?;
+            output.push((size, format!("{:x}", context.compute())));
+        }
+        Ok(output)
+    }
+
+    // Returns an error if dpkg won't accept the control archive's compression
+    fn check_control_compression(&self) -> Result<(), DebError> {
+        match self.control_compression.supports_control() {
+            true => Ok(()),
+            false => Err(DebError::UnsupportedCompression(format!(
+                "control.tar{}",
+                self.control_compression.extension()
+            ))),
+        }
+    }
+
+    // Creates the uncompressed control archive, using the sizes and checksums
+    // from `file_sizes_and_hashes()`
+    fn control_tar(&self, sizes: &[(u64, String)]) -> Result<Vec<u8>, DebError> {
+        let epoch = self.build_epoch()?;
         let mut control_tar = tar::Builder::new(Vec::new());
-        let mut data_tar = tar::Builder::new(Vec::new());
 
         let mut hash = String::new();
-
-        // Adding files to data tar
-        for file in &self.data {
-            let mut file_header = tar::Header::new_gnu();
-            file_header.set_size(file.contents().len().try_into().unwrap());
-            file_header.set_mode(*file.mode());
-            file_header.set_mtime(file.mtime());
-            file_header.set_cksum();
-            // We have to strip the root directory if the path is absolute
-            // as the tar library doesn't allow absolute paths
-            if file.path().is_absolute() {
-                match file.path().strip_prefix("/") {
-                    Ok(path) => {
-                        data_tar.append_data(&mut file_header, path, file.contents().as_slice())?;
-                        hash.push_str(format!("{:x}  {}\n", md5::compute(file.contents().as_slice()), path.display()).as_str());
-                    }
-                    Err(e) => {
-                        return Err(Error::new(ErrorKind::Other, e));
-                    }
-                }
-            } else {
-                data_tar.append_data(&mut file_header, file.path(), file.contents().as_slice())?;
-                hash.push_str(format!("{:x}  {}", md5::compute(file.contents().as_slice()), file.path().display()).as_str());
+        for (file, (_, md5sum)) in self.ordered_files().into_iter().zip(sizes) {
+            if *file.kind() != DebFileKind::File {
+                continue;
             }
+            hash.push_str(format!("{}  {}\n", md5sum, archive_path(file).display()).as_str());
+        }
+
+        // Installed-Size is the sum of every file's size, each rounded up to
+        // the nearest kibibyte, unless it has been set manually. A size read
+        // from an existing package is recalculated, as its files may have
+        // changed. Like dpkg, directories and symlinks count as one kibibyte
+        // each, and hardlinks don't count as their contents are already counted.
+        let mut control = self.control.clone();
+        if !control.installed_size_set {
+            control.installed_size = Some(
+                self.ordered_files()
+                    .into_iter()
+                    .zip(sizes)
+                    .map(|(file, (size, _))| match file.kind() {
+                        DebFileKind::File => size.div_ceil(1024),
+                        DebFileKind::Dir | DebFileKind::Symlink(_) => 1,
+                        DebFileKind::Hardlink(_) => 0,
+                    })
+                    .sum(),
+            );
         }
 
         // Creating DebFile's from control, md5sums and scripts
-        let control_file = Some(DebFile::from_buf(self.control.serialize(), "control"));
+        let control_file = Some(DebFile::from_buf(control.serialize(), "control"));
         let md5sums_file = Some(DebFile::from_buf(hash.into_bytes(), "md5sums"));
-        let mut control_vec = vec![
+        let mut conffiles = String::new();
+        for conffile in self.conffiles() {
+            conffiles.push_str(&conffile.path().display().to_string());
+            if conffile.remove_on_upgrade() {
+                conffiles.push_str(" remove-on-upgrade");
+            }
+            conffiles.push('\n');
+        }
+        let conffiles_file = control_member("conffiles", conffiles);
+        let triggers_file = control_member("triggers", join_lines(&self.triggers, "\n"));
+        let shlibs_file = control_member("shlibs", join_lines(&self.shlibs, "\n"));
+        let symbols_file = control_member("symbols", join_lines(&self.symbols, ""));
+        let templates_file = control_member("templates", join_lines(&self.templates, "\n"));
+        let control_vec = vec![
             &control_file,
             &md5sums_file,
+            &conffiles_file,
+            &triggers_file,
+            &shlibs_file,
+            &symbols_file,
+            &templates_file,
             &self.config,
             &self.preinst,
             &self.postinst,
             &self.prerm,
             &self.postrm,
         ];
+        let control_vec = control_vec
+            .into_iter()
+            .flatten()
+            .chain(self.control_files.iter());
 
         // Adding files to control tar
-        for file in control_vec.into_iter().flatten() {
-            let mut file_header = tar::Header::new_gnu();
+        for file in control_vec {
+            let contents = file.read_contents()?;
+            let mut file_header = tar_header(file, epoch)?;
             // We don't have to worry about the path being absolute here as all
             // scripts can only have relative paths using the struct's methods
             file_header.set_path(file.path())?;
-            file_header.set_size(file.contents().len().try_into().unwrap());
-            file_header.set_mode(*file.mode());
-            file_header.set_mtime(file.mtime());
+            file_header.set_size(contents.len().try_into().unwrap());
             file_header.set_cksum();
-            control_tar.append(&file_header, file.contents().as_slice())?;
-        }
-
-        // Compressing tar archives to DebArchive struct
-        match self.compression {
-            DebCompression::Xz => {
-                let mut control_xz = XzEncoder::new(&mut output.control, 9);
-                control_xz.write_all(control_tar.into_inner()?.as_slice())?;
-                control_xz.finish()?;
-                let mut data_xz = XzEncoder::new(&mut output.data, 9);
-                data_xz.write_all(data_tar.into_inner()?.as_slice())?;
-                data_xz.finish()?;
+            control_tar.append(&file_header, contents.as_slice())?;
+        }
+
+        Ok(control_tar.into_inner()?)
+    }
+
+    // Streams the compressed data archive to `output`, using the sizes from
+    // `file_sizes_and_hashes()`
+    fn write_data_tar<W: Write>(&self, sizes: &[(u64, String)], output: W) -> Result<W, DebError> {
+        let epoch = self.build_epoch()?;
+        let encoder = Encoder::new(self.compression, &self.compression_options, output)?;
+        let mut data_tar = tar::Builder::new(encoder);
+
+        for (file, (size, _)) in self.ordered_files().into_iter().zip(sizes) {
+            let mut file_header = tar_header(file, epoch)?;
+            match file.kind() {
+                DebFileKind::File => {}
+                DebFileKind::Dir => {
+                    file_header.set_entry_type(tar::EntryType::Directory);
+                    file_header.set_size(0);
+                    data_tar.append_data(&mut file_header, archive_path(file), std::io::empty())?;
+                    continue;
+                }
+                DebFileKind::Symlink(target) => {
+                    file_header.set_entry_type(tar::EntryType::Symlink);
+                    file_header.set_size(0);
+                    data_tar.append_link(&mut file_header, archive_path(file), target)?;
+                    continue;
+                }
+                DebFileKind::Hardlink(target) => {
+                    // Hardlinks point to other entries in the archive, so their
+                    // targets have to be stripped of the root directory too
+                    let target = target.strip_prefix("/").unwrap_or(target);
+                    file_header.set_entry_type(tar::EntryType::Link);
+                    file_header.set_size(0);
+                    data_tar.append_link(&mut file_header, archive_path(file), target)?;
+                    continue;
+                }
             }
-            DebCompression::Zstd => {
-                zstd::stream::copy_encode(
-                    control_tar.into_inner()?.as_slice(),
-                    &mut output.control,
-                    0,
-                )?;
-                zstd::stream::copy_encode(data_tar.into_inner()?.as_slice(), &mut output.data, 0)?;
+            file_header.set_size(*size);
+            file_header.set_cksum();
+
+            // The tar header already holds the file's size, so the file must
+            // produce exactly that many bytes the second time it's read
+            let mut reader = SizedReader {
+                inner: file.open()?,
+                remaining: *size,
+                path: file.path(),
+            };
+            data_tar.append_data(&mut file_header, archive_path(file), &mut reader)?;
+            if reader.inner.read(&mut [0])? != 0 {
+                return Err(DebError::FileChanged(file.path().clone()));
             }
         }
 
-        Ok(output)
+        data_tar.into_inner()?.finish()
+    }
+}
+
+// Returns the line number field `name` starts on in `input`
+pub(crate) fn field_line(input: &str, name: &str) -> usize {
+    input
+        .lines()
+        .position(|line| match line.split_once(':') {
+            Some((key, _)) => key.eq_ignore_ascii_case(name),
+            None => false,
+        })
+        .map_or(1, |index| index + 1)
+}
+
+// Joins items into the contents of a control member, ending each with `separator`
+// and a newline
+fn join_lines<T: std::fmt::Display>(items: &[T], separator: &str) -> String {
+    items
+        .iter()
+        .map(|item| format!("{}{}", item, separator))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+// Creates a control member named `name`, unless it would be empty
+fn control_member(name: &str, contents: String) -> Option<DebFile> {
+    match contents.is_empty() {
+        true => None,
+        false => Some(DebFile::from_buf(contents.into_bytes(), name)),
+    }
+}
+
+// Creates a tar header with the mode, modification time and ownership of
+// `file`. If `epoch` is set, the build is reproducible, so the modification
+// time is clamped to it and the file is owned by root.
+fn tar_header(file: &DebFile, epoch: Option<u64>) -> Result<tar::Header, DebError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(*file.mode());
+    match epoch {
+        Some(epoch) => {
+            header.set_mtime(file.mtime().min(epoch));
+            header.set_uid(0);
+            header.set_gid(0);
+            header.set_username("root")?;
+            header.set_groupname("root")?;
+        }
+        None => {
+            header.set_mtime(file.mtime());
+            header.set_uid(file.uid());
+            header.set_gid(file.gid());
+            header.set_username(file.uname())?;
+            header.set_groupname(file.gname())?;
+        }
+    }
+    Ok(header)
+}
+
+// Returns the path of `file` inside the data archive. We have to strip the
+// root directory if the path is absolute as the tar library doesn't allow
+// absolute paths.
+fn archive_path(file: &DebFile) -> &Path {
+    match file.path().strip_prefix("/") {
+        Ok(path) if path.as_os_str().is_empty() => Path::new("."),
+        Ok(path) => path,
+        Err(_) => file.path(),
+    }
+}
+
+// Converts a path inside the data archive back to the absolute path it's
+// installed to. Archives built by dpkg prefix every path with "./", and the
+// root directory itself is stored as ".". Paths which could escape the root
+// directory are rejected, as archives may come from untrusted sources.
+fn install_path(path: &Path) -> Result<PathBuf, DebError> {
+    let mut output = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => output.push(name),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(DebError::UnsafePath(path.to_path_buf()))
+            }
+        }
+    }
+    Ok(output)
+}
+
+// Returns an error once more than `remaining` bytes have been read from
+// `inner`, so decompression bombs can't exhaust memory
+struct LimitedReader<R: Read> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)? as u64;
+        if read > self.remaining {
+            return Err(DebError::LimitExceeded("maximum decompressed size".to_string()).into());
+        }
+        self.remaining -= read;
+        Ok(read as usize)
+    }
+}
+
+// Decompresses `input`, also limiting its decompressed size if `limits` sets one
+fn decompress_limited<'a, R: Read + 'a>(
+    compression: DebCompression,
+    input: R,
+    limits: &DebLimits,
+) -> Result<Box<dyn Read + 'a>, DebError> {
+    let output = decompress(compression, input, limits)?;
+    Ok(match limits.max_decompressed_size() {
+        Some(remaining) => Box::new(LimitedReader {
+            inner: output,
+            remaining,
+        }),
+        None => output,
+    })
+}
+
+// Reads exactly `remaining` bytes from `inner`, returning an error if it ends early
+struct SizedReader<'a, R: Read> {
+    inner: R,
+    remaining: u64,
+    path: &'a PathBuf,
+}
+
+impl<R: Read> Read for SizedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(DebError::FileChanged(self.path.clone()).into());
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+// The global header every ar archive starts with
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+// Writes an ar member header in the format dpkg expects: owned by root, with
+// mode 100644 and a modification time of 0
+pub(crate) fn write_ar_header<W: Write>(
+    output: &mut W,
+    name: &str,
+    size: u64,
+) -> Result<(), DebError> {
+    // The name and size fields are 16 and 10 characters wide, and anything
+    // longer would shift the rest of the header
+    if name.len() > 16 {
+        return Err(DebError::ArHeaderOverflow(format!(
+            "member name {} is longer than 16 bytes",
+            name
+        )));
+    }
+    if size > 9_999_999_999 {
+        return Err(DebError::ArHeaderOverflow(format!(
+            "member {} is {} bytes, larger than 9999999999",
+            name, size
+        )));
+    }
+    writeln!(
+        output,
+        "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}`",
+        name, 0, 0, 0, 33188, size
+    )?;
+    Ok(())
+}
+
+// Writes a complete ar member, padded to an even length
+fn write_ar_member<W: Write>(output: &mut W, name: &str, contents: &[u8]) -> Result<(), DebError> {
+    write_ar_header(output, name, contents.len() as u64)?;
+    output.write_all(contents)?;
+    if contents.len() % 2 == 1 {
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+// Packages are compared by their metadata, files and the contents of their
+// scripts. Installed-Size is calculated when a package is built, so it's only
+// compared when it has been set on both packages. This way, a package that's
+// built and read back compares equal to the original.
+impl PartialEq for DebPackage {
+    fn eq(&self, other: &Self) -> bool {
+        let mut control = other.control.clone();
+        if self.control.installed_size.is_none() || control.installed_size.is_none() {
+            control.installed_size = self.control.installed_size;
+        }
+        control.installed_size_set = self.control.installed_size_set;
+        self.control == control
+            && self.data == other.data
+            && self.config() == other.config()
+            && self.preinst() == other.preinst()
+            && self.postinst() == other.postinst()
+            && self.prerm() == other.prerm()
+            && self.postrm() == other.postrm()
+            && self.conffiles == other.conffiles
+            && self.triggers == other.triggers
+            && self.shlibs == other.shlibs
+            && self.symbols == other.symbols
+            && self.templates == other.templates
+            && self.control_files == other.control_files
+            && self.compression == other.compression
+            && self.control_compression == other.control_compression
+    }
+}
+
+// Detects the compression of an ar member from its contents, falling back to
+// its name, as some tools name members inconsistently with their contents
+fn detect_compression(identifier: &[u8], contents: &[u8]) -> Result<DebCompression, DebError> {
+    if let Some(compression) = DebCompression::from_magic(contents) {
+        return Ok(compression);
+    }
+    match DebCompression::from_name(&String::from_utf8_lossy(identifier)) {
+        Some(compression) => Ok(compression),
+        None => Err(DebError::UnsupportedCompression(
+            String::from_utf8_lossy(identifier).trim().to_string(),
+        )),
     }
 }
 
@@ -984,107 +2158,371 @@ impl DebPackage {
 /// you must first convert it to a DebPackage with the `to_package()` method, or open the
 /// file using DebPackage's `from()` function.
 pub struct DebArchive {
-    control: Vec<u8>,            // Compressed tar archive containing package's metadata
-    data: Vec<u8>,               // Compressed tar archive containing the package's contents
-    compression: DebCompression, // Configuration for the package's compression standard
+    control: Vec<u8>, // Compressed tar archive containing package's metadata
+    data: Vec<u8>,    // Compressed tar archive containing the package's contents
+    control_compression: DebCompression, // Compression standard of the control archive
+    data_compression: DebCompression, // Compression standard of the data archive
+    format_version: Vec<u8>, // Contents of the debian-binary member
+    extra_members: Vec<(String, Vec<u8>)>, // Additional `_` prefixed members, such as signatures
+    extra_before_control: usize, // Number of additional members before the control archive
+    extra_before_data: usize, // Number of additional members before the data archive
+    limits: DebLimits, // Limits used when converting the archive to a DebPackage
 }
 
 impl DebArchive {
     /// Writes package to `output`.
-    pub fn write<W: Write>(&self, mut output: W) -> std::io::Result<()> {
-        // Parsing the name of the control and data archives
-        let (control_name, data_name) = match self.compression {
-            DebCompression::Xz => ("control.tar.xz", "data.tar.xz"),
-            DebCompression::Zstd => ("control.tar.zst", "data.tar.zst"),
-        };
+    pub fn write<W: Write>(&self, mut output: W) -> Result<(), DebError> {
+        output.write_all(AR_MAGIC)?;
+        for (name, contents) in self.members() {
+            write_ar_member(&mut output, &name, contents)?;
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Reads package from `input`.
+    ///
+    /// The compression of the control and data archives is detected separately
+    /// for each archive, from its contents or, failing that, its name.
+    ///
+    /// Members are found by name, as dpkg does. Additional members whose names
+    /// start with `_`, such as the `_gpgorigin` signatures added by debsigs, are
+    /// kept, and `write()` writes them back in the same positions. So is the
+    /// contents of the `debian-binary` member, so a package that's read and
+    /// written again is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a valid ar archive, if
+    /// its format version isn't 2.x, if the control or data archive is missing,
+    /// or if it has any other members.
+    pub fn read<R: Read>(input: R) -> Result<Self, DebError> {
+        Self::read_with_limits(input, DebLimits::new())
+    }
 
-        // Creating final archive
-        let mut archive = ar::Builder::new(Vec::new());
+    /// Reads package from `input`, returning an error if it exceeds `limits`.
+    ///
+    /// The limits are kept and also apply when the archive is converted with
+    /// `to_package()`. Use this instead of `read()` for packages from untrusted
+    /// sources.
+    pub fn read_with_limits<R: Read>(mut input: R, limits: DebLimits) -> Result<Self, DebError> {
+        let mut extra_members: Vec<(String, Vec<u8>)> = Vec::new();
+        let format_version = read_format_version(&mut input)?;
 
-        // Frankly not quite sure what the "debian-binary" file is for,
-        // but it just contains the text "2.0"
-        let mut header = ar::Header::new("debian-binary".as_bytes().to_vec(), 4);
-        header.set_mode(33188);
-        archive.append(&header, "2.0\n".as_bytes())?;
+        // Reading control archive
+        let (name, size) = find_ar_member(&mut input, "control.tar", &mut extra_members, &limits)?;
+        let extra_before_control = extra_members.len();
+        let control = read_ar_contents(&mut input, size, &limits)?;
+        let control_compression = detect_compression(name.as_bytes(), &control)?;
 
-        // Adding control to archive
-        let mut header = ar::Header::new(
-            control_name.as_bytes().to_vec(),
-            self.control.len().try_into().unwrap(),
-        );
-        header.set_mode(33188);
-        archive.append(&header, self.control.as_slice())?;
+        // Reading data archive
+        let (name, size) = find_ar_member(&mut input, "data.tar", &mut extra_members, &limits)?;
+        let extra_before_data = extra_members.len();
+        let data = read_ar_contents(&mut input, size, &limits)?;
+        let data_compression = detect_compression(name.as_bytes(), &data)?;
+
+        // Anything after the data archive must be an additional member
+        while let Some((name, size)) = read_ar_header(&mut input)? {
+            if !name.starts_with('_') {
+                return Err(DebError::MalformedAr(format!("unexpected member {}", name)));
+            }
+            extra_members.push((name, read_ar_contents(&mut input, size, &limits)?));
+        }
 
-        // Adding data to archive
-        let mut header = ar::Header::new(
-            data_name.as_bytes().to_vec(),
-            self.data.len().try_into().unwrap(),
-        );
-        header.set_mode(33188);
-        archive.append(&header, self.data.as_slice())?;
+        Ok(Self {
+            control,
+            data,
+            control_compression,
+            data_compression,
+            format_version,
+            extra_members,
+            extra_before_control,
+            extra_before_data,
+            limits,
+        })
+    }
 
-        // Writing archive to `out`
-        output.write(&archive.into_inner()?);
-        Ok(())
+    /// Returns the archive's additional `_` prefixed members, such as signatures,
+    /// as pairs of their names and contents.
+    pub fn extra_members(&self) -> &Vec<(String, Vec<u8>)> {
+        &self.extra_members
     }
 
-    /// Reads package from `input`.
-    pub fn read<R: Read>(mut input: R) -> std::io::Result<Self> {
-        // Preparing output
-        let mut output = Self {
-            control: Vec::new(),
-            data: Vec::new(),
-            compression: DebCompression::Zstd,
+    /// Signs the archive with `key`, adding a signature member of `kind`.
+    ///
+    /// Any existing signature of the same kind is replaced. As signatures don't
+    /// cover other signatures, a package can be signed with both kinds, or
+    /// signed again by another party, without invalidating earlier signatures.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), deb_rust::DebError> {
+    /// use std::fs::File;
+    /// use deb_rust::{DebKeyring, DebSigningKey};
+    /// use deb_rust::binary::{DebArchive, DebSignatureKind};
+    ///
+    /// let mut archive = DebArchive::read(File::open("example.deb")?)?;
+    /// archive.sign(DebSignatureKind::Origin, &DebSigningKey::from_path("signing-key.asc")?);
+    /// archive.write(File::create("example.deb")?)?;
+    ///
+    /// let keyring = DebKeyring::from_path("trusted.gpg")?;
+    /// for signature in archive.verify_signatures(&keyring)? {
+    ///     println!("{:?} signed by {}", signature.kind(), signature.key().fingerprint());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sign(&mut self, kind: DebSignatureKind, key: &DebSigningKey) {
+        let signature = match kind {
+            DebSignatureKind::Origin => key.sign_binary(&self.origin_contents()),
+            DebSignatureKind::Builder => {
+                let mut files = String::new();
+                for (name, contents) in self.builder_members() {
+                    let digests = DebDigests::from_buf(contents);
+                    files.push_str(&format!(
+                        "\n\t{} {} {} {}",
+                        digests.md5(),
+                        digests.sha1(),
+                        digests.size(),
+                        name
+                    ));
+                }
+                let mut manifest = DebParagraph::new();
+                manifest.set("Version", "4");
+                manifest.set(
+                    "Signer",
+                    key.public_key()
+                        .user_ids()
+                        .first()
+                        .map_or("", String::as_str),
+                );
+                manifest.set(
+                    "Date",
+                    &chrono::Utc::now()
+                        .format("%a %b %e %H:%M:%S %Y")
+                        .to_string(),
+                );
+                manifest.set("Role", "builder");
+                manifest.set("Files", &files);
+                key.clearsign(&manifest.serialize()).into_bytes()
+            }
         };
+        let name = kind.member_name();
+        match self
+            .extra_members
+            .iter_mut()
+            .find(|(member, _)| member == name)
+        {
+            Some((_, contents)) => *contents = signature,
+            None => self.extra_members.push((name.to_string(), signature)),
+        }
+    }
 
-        // Creating Archive reader and iterator
-        let mut archive = ar::Archive::new(input);
-
-        // Skipping `debian-binary` file
-        archive.next_entry().unwrap()?;
+    /// Returns the kinds of signature the archive contains.
+    pub fn signatures(&self) -> Vec<DebSignatureKind> {
+        [DebSignatureKind::Origin, DebSignatureKind::Builder]
+            .into_iter()
+            .filter(|kind| self.signature_member(*kind).is_some())
+            .collect()
+    }
 
-        // Reading control archive
-        match archive.next_entry() {
-            Some(entry) => {
-                entry?.read_to_end(&mut output.control)?;
-            }
-            None => {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "deb package is missing archive",
-                ));
+    /// Verifies the archive's signature of `kind` against `keyring`, returning
+    /// the key which made it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive has no signature of
+    /// `kind`, if it wasn't made by a key in `keyring`, or if it doesn't match
+    /// the archive's members.
+    pub fn verify_signature(
+        &self,
+        kind: DebSignatureKind,
+        keyring: &DebKeyring,
+    ) -> Result<DebSignature, DebError> {
+        let name = kind.member_name();
+        let signature = match self.signature_member(kind) {
+            Some(signature) => signature,
+            None => return Err(DebError::MissingMember(name.to_string())),
+        };
+        let key = match kind {
+            DebSignatureKind::Origin => keyring.verify(&self.origin_contents(), signature)?,
+            DebSignatureKind::Builder => {
+                let (manifest, key) =
+                    keyring.verify_clearsigned(&String::from_utf8_lossy(signature))?;
+                self.check_builder_manifest(&manifest)?;
+                key
             }
-        }
+        };
+        Ok(DebSignature {
+            kind,
+            key: key.clone(),
+        })
+    }
 
-        // Reading data archive
-        let mut data_entry = match archive.next_entry() {
-            Some(entry) => entry?,
-            None => {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "deb package is missing archive",
-                ))
+    /// Verifies every signature in the archive against `keyring`, returning the
+    /// keys which made them. An unsigned archive returns an empty list.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any signature is invalid, under the
+    /// same conditions as `verify_signature()`.
+    pub fn verify_signatures(&self, keyring: &DebKeyring) -> Result<Vec<DebSignature>, DebError> {
+        self.signatures()
+            .into_iter()
+            .map(|kind| self.verify_signature(kind, keyring))
+            .collect()
+    }
+
+    // Returns every member in the order they're written, with additional members
+    // in the positions they were read from
+    fn members(&self) -> Vec<(String, &[u8])> {
+        let [version, control, data] = self.package_members();
+        let extra = self
+            .extra_members
+            .iter()
+            .map(|(name, contents)| (name.clone(), contents.as_slice()));
+        let mut output = vec![version];
+        output.extend(extra.clone().take(self.extra_before_control));
+        output.push(control);
+        output.extend(
+            extra
+                .clone()
+                .take(self.extra_before_data)
+                .skip(self.extra_before_control),
+        );
+        output.push(data);
+        output.extend(extra.skip(self.extra_before_data));
+        output
+    }
+
+    // Returns the debian-binary, control and data members, which every package has
+    fn package_members(&self) -> [(String, &[u8]); 3] {
+        [
+            // Frankly not quite sure what the "debian-binary" file is for,
+            // but it just contains the format version, usually "2.0"
+            ("debian-binary".to_string(), &self.format_version),
+            (
+                format!("control.tar{}", self.control_compression.extension()),
+                &self.control,
+            ),
+            (
+                format!("data.tar{}", self.data_compression.extension()),
+                &self.data,
+            ),
+        ]
+    }
+
+    // Returns the members a builder signature covers: every member other than
+    // the signatures themselves
+    fn builder_members(&self) -> Vec<(String, &[u8])> {
+        let mut output = self.package_members().to_vec();
+        for (name, contents) in &self.extra_members {
+            if !name.starts_with("_gpg") {
+                output.push((name.clone(), contents));
             }
+        }
+        output
+    }
+
+    // Returns the data an origin signature is made over, the concatenated
+    // contents of the debian-binary, control and data members
+    fn origin_contents(&self) -> Vec<u8> {
+        self.package_members()
+            .iter()
+            .flat_map(|(_, contents)| contents.iter().copied())
+            .collect()
+    }
+
+    // Returns the contents of the signature member of `kind`, if it exists
+    fn signature_member(&self, kind: DebSignatureKind) -> Option<&[u8]> {
+        self.extra_members
+            .iter()
+            .find(|(name, _)| name == kind.member_name())
+            .map(|(_, contents)| contents.as_slice())
+    }
+
+    // Checks that the manifest signed by a builder signature lists every member
+    // other than the signatures, with matching sizes and checksums
+    fn check_builder_manifest(&self, manifest: &str) -> Result<(), DebError> {
+        let name = DebSignatureKind::Builder.member_name();
+        let invalid = |line: usize, reason: &str| DebError::InvalidMember {
+            member: name.to_string(),
+            line,
+            reason: reason.to_string(),
+        };
+        let paragraph = DebParagraph::parse(manifest).map_err(|e| e.in_member(name, 1))?;
+        if paragraph.get("Version") != Some("4") {
+            return Err(invalid(
+                field_line(manifest, "Version"),
+                "unsupported version",
+            ));
+        }
+        if paragraph.get("Role") != Some("builder") {
+            return Err(invalid(field_line(manifest, "Role"), "role isn't builder"));
+        }
+        let files = match paragraph.get("Files") {
+            Some(files) => files,
+            None => return Err(invalid(1, "missing Files field")),
         };
-        data_entry.read_to_end(&mut output.data)?;
 
-        // Reading data header to parse compression
-        let mut data_identifier = String::new();
-        match String::from_utf8(data_entry.header().identifier().to_vec()) {
-            Ok(id) => {
-                data_identifier = id;
+        let mut members = self.builder_members();
+        for (index, line) in files.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
             }
-            Err(e) => {
-                return Err(Error::new(ErrorKind::Other, e));
+            let (md5, sha1, size, member) = match line.split_whitespace().collect::<Vec<&str>>()[..]
+            {
+                [md5, sha1, size, member] => (md5, sha1, size, member),
+                _ => {
+                    return Err(invalid(
+                        field_line(manifest, "Files") + index,
+                        &format!("invalid entry {}", line.trim()),
+                    ))
+                }
+            };
+            let contents = match members.iter().position(|(name, _)| name == member) {
+                Some(position) => members.remove(position).1,
+                None => return Err(DebError::BadSignature),
+            };
+            let digests = DebDigests::from_buf(contents);
+            if digests.md5() != md5 || digests.sha1() != sha1 || digests.size().to_string() != size
+            {
+                return Err(DebError::BadSignature);
             }
         }
-        if let Some(ext) = Path::new(&data_identifier).extension() {
-            if ext.to_str() == Some("xz") {
-                output.compression = DebCompression::Xz;
-            }
+        // Members which aren't signed could have been added after signing
+        match members.is_empty() {
+            true => Ok(()),
+            false => Err(DebError::BadSignature),
         }
+    }
 
-        Ok(output)
+    /// Calculates the size and checksums of the .deb file, as written by `write()`.
+    pub fn digests(&self) -> Result<DebDigests, DebError> {
+        let mut writer = DigestWriter::new();
+        self.write(&mut writer)?;
+        Ok(writer.finish())
+    }
+
+    /// Calculates the size and checksums of the compressed control archive.
+    pub fn control_digests(&self) -> DebDigests {
+        DebDigests::from_buf(&self.control)
+    }
+
+    /// Calculates the size and checksums of the compressed data archive.
+    pub fn data_digests(&self) -> DebDigests {
+        DebDigests::from_buf(&self.data)
+    }
+
+    /// Returns the compression standard of the archive's control archive.
+    pub fn control_compression(&self) -> &DebCompression {
+        &self.control_compression
+    }
+
+    /// Returns the compression standard of the archive's data archive.
+    pub fn data_compression(&self) -> &DebCompression {
+        &self.data_compression
     }
 
     /// Converts DebArchive to DebPackage.
@@ -1094,63 +2532,612 @@ impl DebArchive {
     /// This function may return an error if the archive's control file (where all
     /// of a package's metadata is stored) contains invalid syntax, or if part of the
     /// package is corrupted and can't be read.
-    pub fn to_package(&self) -> std::io::Result<DebPackage> {
+    pub fn to_package(&self) -> Result<DebPackage, DebError> {
+        let mut output = self.control_package()?;
+
+        // Converting data entries to DebFile structs
+        let mut data_tar = tar::Archive::new(decompress_limited(
+            self.data_compression,
+            self.data.as_slice(),
+            &self.limits,
+        )?);
+        for entry in (DebEntries {
+            entries: data_tar.entries()?,
+            remaining: self.limits.max_entries(),
+            symlinks: HashSet::new(),
+        }) {
+            output.data.push(entry?.into_file()?);
+        }
+        output.mark_conffiles();
+
+        Ok(output)
+    }
+
+    // Converts only the control archive to a DebPackage, which has no files
+    pub(crate) fn control_package(&self) -> Result<DebPackage, DebError> {
         let mut output = DebPackage::new("");
-        output.compression = match self.compression {
-            DebCompression::Xz => DebCompression::Xz,
-            DebCompression::Zstd => DebCompression::Zstd,
-        };
+        output.compression = self.data_compression;
+        output.control_compression = self.control_compression;
+        read_control_tar(
+            &mut output,
+            decompress_limited(
+                self.control_compression,
+                self.control.as_slice(),
+                &self.limits,
+            )?,
+            &self.limits,
+        )?;
+        Ok(output)
+    }
+}
+
+/// A kind of signature embedded in a package, as an additional `_gpg` prefixed member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebSignatureKind {
+    /// A [debsig-verify][1] origin signature, stored in `_gpgorigin`. It's a
+    /// detached signature of the concatenated contents of the `debian-binary`,
+    /// control and data members.
+    ///
+    /// [1]: https://manpages.debian.org/unstable/debsig-verify/debsig-verify.1.en.html
+    Origin,
+    /// A [dpkg-sig][1] builder signature, stored in `_gpgbuilder`. It's a
+    /// cleartext signed list of the sizes and checksums of every other member.
+    ///
+    /// [1]: https://manpages.debian.org/unstable/dpkg-sig/dpkg-sig.1.en.html
+    Builder,
+}
 
-        // Decompressing control and data archives
-        let mut control_buf: Vec<u8> = Vec::new();
-        let mut data_buf: Vec<u8> = Vec::new();
-        match self.compression {
-            DebCompression::Xz => {
-                XzDecoder::new(self.control.as_slice()).read_to_end(&mut control_buf)?;
-                XzDecoder::new(self.data.as_slice()).read_to_end(&mut data_buf)?;
+impl DebSignatureKind {
+    /// Returns the name of the member the signature is stored in.
+    pub fn member_name(&self) -> &'static str {
+        match self {
+            DebSignatureKind::Origin => "_gpgorigin",
+            DebSignatureKind::Builder => "_gpgbuilder",
+        }
+    }
+}
+
+/// A verified signature embedded in a package, returned by DebArchive's
+/// `verify_signature()` method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebSignature {
+    kind: DebSignatureKind,
+    key: DebPublicKey, // The key which made the signature
+}
+
+impl DebSignature {
+    /// Returns the kind of signature.
+    pub fn kind(&self) -> DebSignatureKind {
+        self.kind
+    }
+
+    /// Returns the key which made the signature.
+    pub fn key(&self) -> &DebPublicKey {
+        &self.key
+    }
+}
+
+// Members of the control archive which are parsed into a DebPackage's fields
+const CONTROL_MEMBERS: [&str; 12] = [
+    "control",
+    "conffiles",
+    "md5sums",
+    "triggers",
+    "shlibs",
+    "symbols",
+    "templates",
+    "config",
+    "preinst",
+    "postinst",
+    "prerm",
+    "postrm",
+];
+
+// Parses the scripts and control file from a decompressed control archive into
+// `package`, returning an error if it has more entries than `limits` allows
+fn read_control_tar<R: Read>(
+    package: &mut DebPackage,
+    input: R,
+    limits: &DebLimits,
+) -> Result<(), DebError> {
+    let mut control_tar = tar::Archive::new(input);
+    let mut remaining = limits.max_entries();
+    for entry_result in control_tar.entries()? {
+        match remaining {
+            Some(0) => {
+                return Err(DebError::LimitExceeded(
+                    "maximum number of entries".to_string(),
+                ))
             }
-            DebCompression::Zstd => {
-                zstd::stream::copy_decode(self.control.as_slice(), &mut control_buf)?;
-                zstd::stream::copy_decode(self.data.as_slice(), &mut data_buf)?;
+            Some(ref mut remaining) => *remaining -= 1,
+            None => {}
+        }
+        let mut entry = entry_result?;
+        // Some tools prefix paths in the control archive with "./"
+        let path = entry.path()?.into_owned();
+        let path = path.strip_prefix("./").unwrap_or(&path);
+
+        // Other members are kept as they are, as long as they're files with
+        // relative paths which stay inside the control archive. They're checked
+        // before their contents are read, and anything else is skipped unread.
+        if !CONTROL_MEMBERS
+            .iter()
+            .any(|member| path == Path::new(member))
+        {
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if path
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+            {
+                return Err(DebError::UnsafePath(path.to_path_buf()));
             }
+        }
+        let mut buf: Vec<u8> = Vec::new();
+        entry.read_to_end(&mut buf)?;
+
+        if path == Path::new("control") {
+            // Converting control file into DebControl struct
+            package.control = DebControl::deserialize(buf)?;
+        } else if path == Path::new("conffiles") {
+            package.conffiles = DebConffile::parse_list(&String::from_utf8_lossy(&buf))?;
+        } else if path == Path::new("md5sums") {
+            package.md5sums = Some(parse_md5sums(&String::from_utf8_lossy(&buf))?);
+        } else if path == Path::new("triggers") {
+            package.triggers = DebTrigger::from_list(&String::from_utf8_lossy(&buf))?;
+        } else if path == Path::new("shlibs") {
+            package.shlibs = DebShlib::from_list(&String::from_utf8_lossy(&buf))?;
+        } else if path == Path::new("symbols") {
+            package.symbols = DebSymbols::from_list(&String::from_utf8_lossy(&buf))?;
+        } else if path == Path::new("templates") {
+            package.templates = DebTemplate::from_list(&String::from_utf8_lossy(&buf))?;
+        } else if path == Path::new("config") {
+            package.config = Some(DebFile::from_buf(buf, "config").is_exec());
+        } else if path == Path::new("preinst") {
+            package.preinst = Some(DebFile::from_buf(buf, "preinst").is_exec());
+        } else if path == Path::new("postinst") {
+            package.postinst = Some(DebFile::from_buf(buf, "postinst").is_exec());
+        } else if path == Path::new("prerm") {
+            package.prerm = Some(DebFile::from_buf(buf, "prerm").is_exec());
+        } else if path == Path::new("postrm") {
+            package.postrm = Some(DebFile::from_buf(buf, "postrm").is_exec());
+        } else {
+            package.control_files.push(
+                DebFile::from_buf(buf, path)
+                    .set_mode(entry.header().mode()?)
+                    .set_mtime(entry.header().mtime()?),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Parses the `md5sums` member of a control archive
+fn parse_md5sums(input: &str) -> Result<Vec<(PathBuf, String)>, DebError> {
+    let mut output = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.split_once(char::is_whitespace) {
+            Some((md5sum, path)) if md5sum.len() == 32 => output.push((
+                install_path(Path::new(path.trim_start()))?,
+                md5sum.to_string(),
+            )),
+            _ => {
+                return Err(DebError::InvalidMember {
+                    member: "md5sums".to_string(),
+                    line: index + 1,
+                    reason: format!("invalid entry {}", line),
+                })
+            }
+        }
+    }
+    Ok(output)
+}
+
+// Reads the next ar member header from `input`, returning the member's name
+// and size, or `None` if the end of the archive has been reached
+fn read_ar_header<R: Read>(input: &mut R) -> Result<Option<(String, u64)>, DebError> {
+    let mut header = [0; 60];
+    let mut read = 0;
+    while read < header.len() {
+        match input.read(&mut header[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => {
+                return Err(DebError::MalformedAr(
+                    "member header is truncated".to_string(),
+                ))
+            }
+            n => read += n,
+        }
+    }
+    if &header[58..60] != b"`\n" {
+        return Err(DebError::MalformedAr(
+            "member header is invalid".to_string(),
+        ));
+    }
+    // GNU ar terminates names with a slash, while BSD ar pads them with spaces
+    let name = String::from_utf8_lossy(&header[0..16]);
+    let name = name.trim_end().trim_end_matches('/').to_string();
+    let size = match String::from_utf8_lossy(&header[48..58])
+        .trim()
+        .parse::<u64>()
+    {
+        Ok(size) => size,
+        Err(_) => return Err(DebError::MalformedAr("member size is invalid".to_string())),
+    };
+    Ok(Some((name, size)))
+}
+
+// Skips the padding byte that follows odd sized ar members. Some tools leave
+// it out after the last member, so reaching the end of `input` is fine.
+fn skip_ar_padding<R: Read>(input: &mut R, size: u64) -> Result<(), DebError> {
+    if size % 2 == 1 {
+        input.by_ref().take(1).read_to_end(&mut Vec::new())?;
+    }
+    Ok(())
+}
+
+// Reads the contents of an ar member of `size` bytes, and its padding
+fn read_ar_contents<R: Read>(
+    input: &mut R,
+    size: u64,
+    limits: &DebLimits,
+) -> Result<Vec<u8>, DebError> {
+    if limits.max_member_size().is_some_and(|max| size > max) {
+        return Err(DebError::LimitExceeded("maximum member size".to_string()));
+    }
+    let mut contents: Vec<u8> = Vec::new();
+    input.by_ref().take(size).read_to_end(&mut contents)?;
+    if (contents.len() as u64) < size {
+        return Err(DebError::MalformedAr("member is truncated".to_string()));
+    }
+    skip_ar_padding(input, size)?;
+    Ok(contents)
+}
+
+// Reads the ar global header and the `debian-binary` member, checking that the
+// package uses format version 2.x, the only version dpkg supports. Returns the
+// member's contents.
+fn read_format_version<R: Read>(input: &mut R) -> Result<Vec<u8>, DebError> {
+    let mut magic: Vec<u8> = Vec::new();
+    input.by_ref().take(8).read_to_end(&mut magic)?;
+    if magic != AR_MAGIC {
+        return Err(DebError::MalformedAr("missing global header".to_string()));
+    }
+
+    let size = match read_ar_header(input)? {
+        Some((name, size)) if name == "debian-binary" => size,
+        Some((name, _)) => {
+            return Err(DebError::MalformedAr(format!(
+                "expected debian-binary, found {}",
+                name
+            )))
+        }
+        None => return Err(DebError::MissingMember("debian-binary".to_string())),
+    };
+    // The version is only a few bytes, so anything larger is left unread
+    // rather than loaded into memory
+    if size > 1024 {
+        return Err(DebError::MalformedAr(
+            "debian-binary is too large".to_string(),
+        ));
+    }
+    let contents = read_ar_contents(input, size, &DebLimits::new())?;
+    let version = String::from_utf8_lossy(&contents);
+    let version = version.lines().next().unwrap_or_default().trim();
+    match version.split_once('.') {
+        Some(("2", minor)) if !minor.is_empty() && minor.chars().all(|c| c.is_ascii_digit()) => {
+            Ok(contents)
+        }
+        _ => Err(DebError::UnsupportedFormat(version.to_string())),
+    }
+}
+
+// Reads ar members until the one named `name`, optionally followed by a
+// compression extension, returning its name and size. Additional `_` prefixed
+// members before it are added to `extra`, and any other member is an error.
+fn find_ar_member<R: Read>(
+    input: &mut R,
+    name: &str,
+    extra: &mut Vec<(String, Vec<u8>)>,
+    limits: &DebLimits,
+) -> Result<(String, u64), DebError> {
+    loop {
+        let (member, size) = match read_ar_header(input)? {
+            Some(header) => header,
+            None => return Err(DebError::MissingMember(name.to_string())),
         };
-        let mut control_tar = tar::Archive::new(control_buf.as_slice());
-        let mut data_tar = tar::Archive::new(data_buf.as_slice());
-
-        // Parsing control archive
-        for entry_result in control_tar.entries()? {
-            let mut entry = entry_result?;
-            let mut buf: Vec<u8> = Vec::new();
-            entry.read_to_end(&mut buf);
-            if entry.path()? == Cow::Borrowed(Path::new("control")) {
-                // Converting control file into DebControl struct
-                output.control = DebControl::deserialize(buf)?;
-            } else if entry.path()? == Cow::Borrowed(Path::new("config")) {
-                output = output.config_from_buf(buf);
-            } else if entry.path()? == Cow::Borrowed(Path::new("preinst")) {
-                output = output.preinst_from_buf(buf);
-            } else if entry.path()? == Cow::Borrowed(Path::new("postinst")) {
-                output = output.postinst_from_buf(buf);
-            } else if entry.path()? == Cow::Borrowed(Path::new("prerm")) {
-                output = output.prerm_from_buf(buf);
-            } else if entry.path()? == Cow::Borrowed(Path::new("postrm")) {
-                output = output.postrm_from_buf(buf);
+        if member == name
+            || member
+                .strip_prefix(name)
+                .is_some_and(|extension| extension.starts_with('.'))
+        {
+            return Ok((member, size));
+        }
+        if !member.starts_with('_') {
+            return Err(DebError::MalformedAr(format!(
+                "unexpected member {} before {}",
+                member, name
+            )));
+        }
+        extra.push((member, read_ar_contents(input, size, limits)?));
+    }
+}
+
+/// Reads a .deb file lazily, for inspecting packages without loading their
+/// files into memory.
+///
+/// Only the control archive is read when a DebReader is created. The data archive
+/// is decompressed on the fly as its entries are iterated over, so listing the
+/// package's files or extracting a single one from a large package is cheap.
+/// As `input` is only read forward, the entries can only be iterated over once.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// use std::fs::File;
+/// use std::io::Read;
+/// use deb_rust::binary::DebReader;
+///
+/// let mut reader = DebReader::new(File::open("example.deb")?)?;
+/// println!("{}", reader.package().name());
+/// for entry in reader.entries()? {
+///     let mut entry = entry?;
+///     if entry.path()?.ends_with("example") {
+///         let mut buf: Vec<u8> = Vec::new();
+///         entry.read_to_end(&mut buf)?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct DebReader<'a> {
+    package: DebPackage, // The package's metadata and scripts, without any files
+    data: tar::Archive<Box<dyn Read + 'a>>, // The decompressing data archive reader
+    limits: DebLimits,   // Limits on the package's resources
+}
+
+impl<'a> DebReader<'a> {
+    /// Creates a DebReader from `input`, reading the package's control archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a valid .deb file, or
+    /// if either archive uses an unsupported compression.
+    pub fn new<R: Read + 'a>(input: R) -> Result<Self, DebError> {
+        Self::with_limits(input, DebLimits::new())
+    }
+
+    /// Creates a DebReader from `input`, returning an error if the package
+    /// exceeds `limits`.
+    ///
+    /// The limits are checked as the package is read, so iterating over its
+    /// entries may also return a `DebError::LimitExceeded` error.
+    pub fn with_limits<R: Read + 'a>(mut input: R, limits: DebLimits) -> Result<Self, DebError> {
+        // Additional members aren't kept, as those after the data archive
+        // can't be reached without reading through it
+        let mut extra_members: Vec<(String, Vec<u8>)> = Vec::new();
+        read_format_version(&mut input)?;
+
+        // Reading control archive
+        let (name, size) = find_ar_member(&mut input, "control.tar", &mut extra_members, &limits)?;
+        let control = read_ar_contents(&mut input, size, &limits)?;
+        let control_compression = detect_compression(name.as_bytes(), &control)?;
+
+        let mut package = DebPackage::new("");
+        package.control_compression = control_compression;
+        read_control_tar(
+            &mut package,
+            decompress_limited(control_compression, control.as_slice(), &limits)?,
+            &limits,
+        )?;
+
+        // Only the start of the data archive is read, to detect its compression
+        let (name, size) = find_ar_member(&mut input, "data.tar", &mut extra_members, &limits)?;
+        let mut start: Vec<u8> = Vec::new();
+        input.by_ref().take(size.min(262)).read_to_end(&mut start)?;
+        package.compression = detect_compression(name.as_bytes(), &start)?;
+        let rest = input.take(size - start.len() as u64);
+        let data = decompress_limited(
+            package.compression,
+            std::io::Cursor::new(start).chain(rest),
+            &limits,
+        )?;
+
+        Ok(Self {
+            package,
+            data: tar::Archive::new(data),
+            limits,
+        })
+    }
+
+    /// Returns the package's metadata and scripts.
+    ///
+    /// The returned DebPackage has no files. Use `entries()` to read them.
+    pub fn package(&self) -> &DebPackage {
+        &self.package
+    }
+
+    /// Returns an iterator over the entries of the package's data archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entries have already been
+    /// iterated over.
+    pub fn entries(&mut self) -> Result<DebEntries<'_, 'a>, DebError> {
+        Ok(DebEntries {
+            entries: self.data.entries()?,
+            remaining: self.limits.max_entries(),
+            symlinks: HashSet::new(),
+        })
+    }
+}
+
+/// An iterator over the entries of a package's data archive, created by
+/// DebReader's `entries()` method.
+///
+/// Entries whose paths could escape the root directory are errors. That
+/// includes entries, and hardlink targets, inside a symlink from earlier in the
+/// archive, such as `usr/x/passwd` after a symlink `usr/x -> ../../etc`.
+pub struct DebEntries<'b, 'a> {
+    entries: tar::Entries<'b, Box<dyn Read + 'a>>,
+    remaining: Option<usize>,   // Entries left before the limit is exceeded
+    symlinks: HashSet<PathBuf>, // Install paths of the symlinks read so far
+}
+
+impl<'b, 'a> DebEntries<'b, 'a> {
+    // Checks that `entry` isn't inside a symlink from earlier in the archive,
+    // which could point anywhere, and records it if it's a symlink itself
+    fn check_symlinks(&mut self, entry: &DebEntry<'b, 'a>) -> Result<(), DebError> {
+        let path = entry.path()?;
+        let mut paths = vec![path.clone()];
+        let entry_type = entry.entry.header().entry_type();
+        if entry_type == tar::EntryType::Link {
+            if let Some(target) = entry.entry.link_name()? {
+                paths.push(install_path(&target)?);
             }
         }
+        for checked in paths {
+            if checked
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| self.symlinks.contains(ancestor))
+            {
+                return Err(DebError::UnsafePath(checked));
+            }
+        }
+        if entry_type == tar::EntryType::Symlink {
+            self.symlinks.insert(path);
+        }
+        Ok(())
+    }
+}
 
-        // Converting data entries to DebFile structs
-        for entry_result in data_tar.entries()? {
-            let mut entry = entry_result?;
-            let mut buf: Vec<u8> = Vec::new();
-            entry.read_to_end(&mut buf);
-            if let Cow::Borrowed(path) = entry.path()? {
-                output.data.push(
-                    DebFile::from_buf(buf, format!("/{}", path.display()))
-                        .set_mode(entry.header().mode()?),
-                )
+impl<'b, 'a> Iterator for DebEntries<'b, 'a> {
+    type Item = Result<DebEntry<'b, 'a>, DebError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        match self.remaining {
+            Some(0) => {
+                return Some(Err(DebError::LimitExceeded(
+                    "maximum number of entries".to_string(),
+                )))
             }
+            Some(ref mut remaining) => *remaining -= 1,
+            None => {}
         }
+        let entry = match entry {
+            Ok(entry) => DebEntry { entry },
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(self.check_symlinks(&entry).map(|_| entry))
+    }
+}
 
-        Ok(output)
+/// A single entry of a package's data archive.
+///
+/// The entry's contents are decompressed as they're read through its `Read`
+/// implementation.
+pub struct DebEntry<'b, 'a> {
+    entry: tar::Entry<'b, Box<dyn Read + 'a>>,
+}
+
+impl DebEntry<'_, '_> {
+    /// Returns the path the entry will be installed to.
+    pub fn path(&self) -> Result<PathBuf, DebError> {
+        install_path(&self.entry.path()?)
+    }
+
+    /// Returns the entry's permissions in octal form.
+    pub fn mode(&self) -> Result<u32, DebError> {
+        Ok(self.entry.header().mode()?)
+    }
+
+    /// Returns the entry's modification time.
+    pub fn mtime(&self) -> Result<u64, DebError> {
+        Ok(self.entry.header().mtime()?)
+    }
+
+    /// Returns what kind of entry this is.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entry isn't a regular file,
+    /// directory, symlink or hardlink, such as a device file.
+    pub fn kind(&self) -> Result<DebFileKind, DebError> {
+        let link_name = || match self.entry.link_name()? {
+            Some(target) => Ok(target.into_owned()),
+            None => Err(DebError::UnsupportedEntry(self.path()?)),
+        };
+        match self.entry.header().entry_type() {
+            tar::EntryType::Regular | tar::EntryType::Continuous => Ok(DebFileKind::File),
+            tar::EntryType::Directory => Ok(DebFileKind::Dir),
+            tar::EntryType::Symlink => Ok(DebFileKind::Symlink(link_name()?)),
+            tar::EntryType::Link => Ok(DebFileKind::Hardlink(install_path(&link_name()?)?)),
+            _ => Err(DebError::UnsupportedEntry(self.path()?)),
+        }
+    }
+
+    /// Returns the ID of the user owning the entry.
+    pub fn uid(&self) -> Result<u64, DebError> {
+        Ok(self.entry.header().uid()?)
+    }
+
+    /// Returns the ID of the group owning the entry.
+    pub fn gid(&self) -> Result<u64, DebError> {
+        Ok(self.entry.header().gid()?)
+    }
+
+    /// Returns the name of the user owning the entry, or an empty string if
+    /// the archive doesn't record it.
+    pub fn uname(&self) -> Result<String, DebError> {
+        match self.entry.header().username() {
+            Ok(name) => Ok(name.unwrap_or_default().to_string()),
+            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()),
+        }
+    }
+
+    /// Returns the name of the group owning the entry, or an empty string if
+    /// the archive doesn't record it.
+    pub fn gname(&self) -> Result<String, DebError> {
+        match self.entry.header().groupname() {
+            Ok(name) => Ok(name.unwrap_or_default().to_string()),
+            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()),
+        }
+    }
+
+    /// Returns the size of the entry's contents in bytes.
+    pub fn size(&self) -> u64 {
+        self.entry.size()
+    }
+
+    /// Reads the entry's contents into memory, converting it to a DebFile.
+    pub fn into_file(mut self) -> Result<DebFile, DebError> {
+        let file = match self.kind()? {
+            DebFileKind::File => {
+                let mut buf: Vec<u8> = Vec::new();
+                self.entry.read_to_end(&mut buf)?;
+                DebFile::from_buf(buf, self.path()?)
+            }
+            DebFileKind::Dir => DebFile::new_dir(self.path()?),
+            DebFileKind::Symlink(target) => DebFile::new_symlink(target, self.path()?),
+            DebFileKind::Hardlink(target) => DebFile::new_hardlink(target, self.path()?),
+        };
+        Ok(file
+            .set_mode(self.mode()?)
+            .set_mtime(self.mtime()?)
+            .set_owner(self.uid()?, &self.uname()?)
+            .set_group(self.gid()?, &self.gname()?))
+    }
+}
+
+impl Read for DebEntry<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.entry.read(buf)
     }
 }
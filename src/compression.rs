@@ -0,0 +1,188 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Compresses and decompresses the members of a Deb archive
+
+use std::io::{Read, Write};
+
+use crate::error::DebError;
+use crate::shared::{DebCompression, DebCompressionOptions, DebLimits};
+
+use xz::stream::{Check, LzmaOptions, MtStreamBuilder, Stream, CONCATENATED};
+
+// liblzma's flag for the extreme variant of a preset
+const LZMA_PRESET_EXTREME: u32 = 0x80000000;
+
+// Memory XZ and LZMA decoders may use on top of their dictionary
+const LZMA_DECODER_OVERHEAD: u64 = 1 << 20;
+
+// Compresses `input` with `compression`, appending the result to `output`
+pub(crate) fn compress(
+    compression: DebCompression,
+    options: &DebCompressionOptions,
+    input: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), DebError> {
+    let mut encoder = Encoder::new(compression, options, output)?;
+    encoder.write_all(input)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+// A writer which compresses everything written to it before passing it on to
+// the inner writer. `finish()` must be called once everything is written.
+pub(crate) enum Encoder<W: Write> {
+    Xz(xz::write::XzEncoder<W>),
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    Gzip(flate2::write::GzEncoder<W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+    None(W),
+}
+
+impl<W: Write> Encoder<W> {
+    // Creates an encoder which writes `compression` compressed data to `output`
+    pub(crate) fn new(
+        compression: DebCompression,
+        options: &DebCompressionOptions,
+        output: W,
+    ) -> Result<Self, DebError> {
+        // Checks the level is within `min..=max`, using `default` if it's unset
+        let level = |min: i32, max: i32, default: i32| match options.level() {
+            Some(level) if level < min || level > max => {
+                Err(DebError::InvalidCompressionLevel(level))
+            }
+            Some(level) => Ok(level),
+            None => Ok(default),
+        };
+        // XZ and LZMA presets, optionally with the extreme flag
+        let preset = || -> Result<u32, DebError> {
+            let preset = level(0, 9, 9)? as u32;
+            Ok(match options.extreme() {
+                true => preset | LZMA_PRESET_EXTREME,
+                false => preset,
+            })
+        };
+
+        Ok(match compression {
+            DebCompression::Xz => {
+                let stream = match options.threads() {
+                    0 | 1 => Stream::new_easy_encoder(preset()?, Check::Crc64)
+                        .map_err(std::io::Error::from)?,
+                    threads => MtStreamBuilder::new()
+                        .threads(threads)
+                        .preset(preset()?)
+                        .check(Check::Crc64)
+                        .encoder()
+                        .map_err(std::io::Error::from)?,
+                };
+                Encoder::Xz(xz::write::XzEncoder::new_stream(output, stream))
+            }
+            DebCompression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, level(-7, 22, 0)?)?;
+                if options.threads() > 1 {
+                    encoder.multithread(options.threads())?;
+                }
+                if options.long_distance_matching() {
+                    encoder.long_distance_matching(true)?;
+                }
+                if let Some(window_log) = options.window_log() {
+                    encoder.window_log(window_log)?;
+                }
+                Encoder::Zstd(encoder)
+            }
+            DebCompression::Gzip => {
+                let level = flate2::Compression::new(level(0, 9, 9)? as u32);
+                Encoder::Gzip(flate2::write::GzEncoder::new(output, level))
+            }
+            DebCompression::Bzip2 => {
+                let level = bzip2::Compression::new(level(1, 9, 9)? as u32);
+                Encoder::Bzip2(bzip2::write::BzEncoder::new(output, level))
+            }
+            DebCompression::Lzma => {
+                let options = LzmaOptions::new_preset(preset()?).map_err(std::io::Error::from)?;
+                let stream = Stream::new_lzma_encoder(&options).map_err(std::io::Error::from)?;
+                Encoder::Xz(xz::write::XzEncoder::new_stream(output, stream))
+            }
+            DebCompression::None => Encoder::None(output),
+        })
+    }
+
+    // Flushes any remaining compressed data and returns the inner writer
+    pub(crate) fn finish(self) -> Result<W, DebError> {
+        Ok(match self {
+            Encoder::Xz(encoder) => encoder.finish()?,
+            Encoder::Zstd(encoder) => encoder.finish()?,
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Bzip2(encoder) => encoder.finish()?,
+            Encoder::None(output) => output,
+        })
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoder::Xz(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Bzip2(encoder) => encoder.write(buf),
+            Encoder::None(output) => output.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoder::Xz(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Bzip2(encoder) => encoder.flush(),
+            Encoder::None(output) => output.flush(),
+        }
+    }
+}
+
+// Wraps `input` in a reader which decompresses it with `compression`. The
+// decompressor's window is limited by `limits`, so a few bytes of crafted input
+// can't make it allocate gigabytes before any other limit applies.
+pub(crate) fn decompress<'a, R: Read + 'a>(
+    compression: DebCompression,
+    input: R,
+    limits: &DebLimits,
+) -> Result<Box<dyn Read + 'a>, DebError> {
+    let memlimit = (1u64 << limits.max_window_log().min(63)) + LZMA_DECODER_OVERHEAD;
+    Ok(match compression {
+        // Multi-stream decoders are used as parallel compressors such as
+        // pixz and pbzip2 write concatenated streams
+        DebCompression::Xz => Box::new(xz::read::XzDecoder::new_stream(
+            input,
+            Stream::new_stream_decoder(memlimit, CONCATENATED).map_err(std::io::Error::from)?,
+        )),
+        DebCompression::Zstd => {
+            let mut decoder = zstd::stream::read::Decoder::new(input)?;
+            decoder.window_log_max(limits.max_window_log())?;
+            Box::new(decoder)
+        }
+        DebCompression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+        DebCompression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(input)),
+        DebCompression::Lzma => Box::new(xz::read::XzDecoder::new_stream(
+            input,
+            Stream::new_lzma_decoder(memlimit).map_err(std::io::Error::from)?,
+        )),
+        DebCompression::None => Box::new(input),
+    })
+}
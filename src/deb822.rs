@@ -0,0 +1,227 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::error::DebError;
+
+/// A single paragraph of a [deb822][1] formatted file, such as a package's control file.
+///
+/// Fields are kept in the order they were read or added. Values of multi-line
+/// fields are stored as they appear in the file: the first line, followed by each
+/// continuation line (including its leading whitespace) separated by `\n`.
+///
+/// [1]: https://manpages.debian.org/unstable/dpkg-dev/deb822.5.en.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebParagraph {
+    fields: Vec<(String, String)>,
+}
+
+impl DebParagraph {
+    /// Creates an empty DebParagraph.
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Parses a single paragraph from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is not valid deb822 syntax,
+    /// or if it contains more than one paragraph.
+    pub fn parse(input: &str) -> Result<Self, DebError> {
+        let mut paragraphs = Self::parse_all(input)?;
+        match paragraphs.len() {
+            0 => Ok(Self::new()),
+            1 => Ok(paragraphs.remove(0)),
+            _ => Err(DebError::InvalidSyntax {
+                line: input
+                    .lines()
+                    .position(|line| line.trim().is_empty())
+                    .unwrap_or_default()
+                    + 1,
+                reason: "expected a single paragraph".to_string(),
+            }),
+        }
+    }
+
+    /// Parses every paragraph in `input`.
+    ///
+    /// Paragraphs are separated by one or more blank lines, and lines starting
+    /// with `#` are treated as comments.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is not valid deb822 syntax.
+    pub fn parse_all(input: &str) -> Result<Vec<Self>, DebError> {
+        let mut output: Vec<Self> = Vec::new();
+        let mut current = Self::new();
+
+        for (index, raw_line) in input.lines().enumerate() {
+            let line = raw_line.trim_end();
+            let line_number = index + 1;
+
+            // Blank lines end the current paragraph
+            if line.is_empty() {
+                if !current.fields.is_empty() {
+                    output.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+
+            if line.starts_with('#') {
+                continue;
+            }
+
+            // Continuation lines belong to the previous field
+            if line.starts_with(' ') || line.starts_with('\t') {
+                match current.fields.last_mut() {
+                    Some((_, value)) => {
+                        value.push('\n');
+                        value.push_str(line);
+                    }
+                    None => {
+                        return Err(DebError::InvalidSyntax {
+                            line: line_number,
+                            reason: "continuation line without a field".to_string(),
+                        });
+                    }
+                }
+                continue;
+            }
+
+            // Only the first colon separates the name from the value, as values
+            // such as URLs may contain colons themselves
+            let (name, value) = match line.split_once(':') {
+                Some(pair) => pair,
+                None => {
+                    return Err(DebError::InvalidSyntax {
+                        line: line_number,
+                        reason: "missing field separator".to_string(),
+                    });
+                }
+            };
+            if name.is_empty()
+                || name.starts_with('-')
+                || name.chars().any(|c| c.is_whitespace() || c.is_control())
+            {
+                return Err(DebError::InvalidSyntax {
+                    line: line_number,
+                    reason: "invalid field name".to_string(),
+                });
+            }
+            if current.get(name).is_some() {
+                return Err(DebError::InvalidSyntax {
+                    line: line_number,
+                    reason: format!("duplicate field {}", name),
+                });
+            }
+            current
+                .fields
+                .push((name.to_string(), value.trim().to_string()));
+        }
+
+        if !current.fields.is_empty() {
+            output.push(current);
+        }
+
+        Ok(output)
+    }
+
+    /// Returns the value of field `name`, if it exists.
+    ///
+    /// Field names are compared case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets field `name` to `value`.
+    ///
+    /// If the field already exists, its value is replaced in place. Otherwise,
+    /// the field is added to the end of the paragraph.
+    ///
+    /// `value` is stored the way it would be read back from a file: lines after
+    /// the first are indented with a space if they aren't already, and empty
+    /// lines are replaced with ` .`.
+    pub fn set(&mut self, name: &str, value: &str) {
+        let value = fold_value(value);
+        match self
+            .fields
+            .iter_mut()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, old)) => *old = value,
+            None => self.fields.push((name.to_string(), value)),
+        }
+    }
+
+    /// Removes field `name`, returning its value if it existed.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .fields
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Returns the paragraph's fields in order.
+    pub fn fields(&self) -> &Vec<(String, String)> {
+        &self.fields
+    }
+
+    /// Returns whether the paragraph has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Converts the paragraph to deb822 syntax.
+    pub fn serialize(&self) -> String {
+        let mut output = String::new();
+        for (name, value) in &self.fields {
+            output.push_str(name);
+            output.push(':');
+            if !value.is_empty() && !value.starts_with('\n') {
+                output.push(' ');
+            }
+            output.push_str(value);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+// Converts a value to the form it takes once written to and read back from a
+// file, so values compare equal regardless of which way they were set
+pub(crate) fn fold_value(value: &str) -> String {
+    let mut lines = value.split('\n');
+    let mut output = lines.next().unwrap_or_default().trim().to_string();
+    for line in lines {
+        let line = line.trim_end();
+        output.push('\n');
+        if line.trim().is_empty() {
+            output.push_str(" .");
+        } else {
+            if !line.starts_with(' ') && !line.starts_with('\t') {
+                output.push(' ');
+            }
+            output.push_str(line);
+        }
+    }
+    output
+}
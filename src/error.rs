@@ -0,0 +1,218 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// The error type returned throughout deb-rust.
+///
+/// DebError can be converted into a `std::io::Error`, so functions returning
+/// `std::io::Result` can use `?` on deb-rust's functions.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DebError {
+    /// An I/O error occurred while reading or writing.
+    Io(std::io::Error),
+    /// A deb822 formatted file, such as a control file, has invalid syntax on `line`.
+    InvalidSyntax { line: usize, reason: String },
+    /// The control file's `field` on `line` has an invalid value.
+    InvalidControlField {
+        field: String,
+        line: usize,
+        reason: String,
+    },
+    /// A version string is invalid.
+    InvalidVersion { version: String, reason: String },
+    /// A relationship, such as a dependency, is invalid.
+    InvalidRelation(String),
+    /// An architecture name isn't known.
+    UnknownArchitecture(String),
+    /// A priority isn't known.
+    UnknownPriority(String),
+    /// A Multi-Arch value isn't known.
+    UnknownMultiArch(String),
+    /// An archive member, named by the contained string, uses an unsupported compression.
+    UnsupportedCompression(String),
+    /// A compression level is outside of the range its compression supports.
+    InvalidCompressionLevel(i32),
+    /// A required member of the package, such as the control file, is missing.
+    MissingMember(String),
+    /// The package isn't a valid ar archive, or its members are out of order.
+    MalformedAr(String),
+    /// The package's format version, from its `debian-binary` member, isn't supported.
+    UnsupportedFormat(String),
+    /// A control member, such as `triggers` or `symbols`, is invalid on `line`.
+    InvalidMember {
+        member: String,
+        line: usize,
+        reason: String,
+    },
+    /// A member's name or size doesn't fit in its ar header, described by the contained string.
+    ArHeaderOverflow(String),
+    /// A package exceeded one of the limits set by DebLimits, described by the contained string.
+    LimitExceeded(String),
+    /// A path in the package would escape the directory it's installed to, such as
+    /// one containing `..`.
+    UnsafePath(PathBuf),
+    /// The data archive has an entry which isn't a regular file, directory, or link.
+    UnsupportedEntry(PathBuf),
+    /// A file changed after its size or checksums were calculated, such as while
+    /// the package was being built.
+    FileChanged(PathBuf),
+    /// The package has no md5sums to verify its files against.
+    MissingMd5sums,
+    /// The `SOURCE_DATE_EPOCH` environment variable isn't a valid timestamp.
+    InvalidSourceDateEpoch(String),
+    /// OpenPGP data, such as a key or signature, is malformed or unsupported.
+    InvalidOpenPgp(String),
+    /// A signature was made by a key, named by its ID or fingerprint, that isn't in the keyring.
+    UnknownSigningKey(String),
+    /// A signature doesn't match the data it signs.
+    BadSignature,
+    /// A signature was made by a revoked key, named by its fingerprint.
+    RevokedKey(String),
+    /// A signature was made by an expired key, named by its fingerprint.
+    ExpiredKey(String),
+    /// A signature has passed its expiry time.
+    ExpiredSignature,
+}
+
+impl DebError {
+    // Converts an error from parsing a line of a control member into an
+    // InvalidMember error, keeping the line number of syntax errors
+    pub(crate) fn in_member(self, member: &str, line: usize) -> Self {
+        match self {
+            DebError::InvalidMember { reason, .. } => DebError::InvalidMember {
+                member: member.to_string(),
+                line,
+                reason,
+            },
+            DebError::InvalidSyntax { line, reason } => DebError::InvalidMember {
+                member: member.to_string(),
+                line,
+                reason,
+            },
+            error => DebError::InvalidMember {
+                member: member.to_string(),
+                line,
+                reason: error.to_string(),
+            },
+        }
+    }
+
+    // Converts an error from parsing a control field's value into an
+    // InvalidControlField error
+    pub(crate) fn in_field(self, field: &str, line: usize) -> Self {
+        let reason = match self {
+            DebError::InvalidControlField { reason, .. } => reason,
+            error => error.to_string(),
+        };
+        DebError::InvalidControlField {
+            field: field.to_string(),
+            line,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for DebError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebError::Io(error) => write!(f, "{}", error),
+            DebError::InvalidSyntax { line, reason } => {
+                write!(f, "invalid syntax on line {}: {}", line, reason)
+            }
+            DebError::InvalidControlField {
+                field,
+                line,
+                reason,
+            } => write!(f, "invalid {} field on line {}: {}", field, line, reason),
+            DebError::InvalidVersion { version, reason } => {
+                write!(f, "invalid version `{}`: {}", version, reason)
+            }
+            DebError::InvalidRelation(relation) => write!(f, "invalid relation `{}`", relation),
+            DebError::UnknownArchitecture(name) => write!(f, "unknown architecture `{}`", name),
+            DebError::UnknownPriority(name) => write!(f, "unknown priority `{}`", name),
+            DebError::UnknownMultiArch(value) => write!(f, "unknown Multi-Arch value `{}`", value),
+            DebError::UnsupportedCompression(name) => {
+                write!(f, "unsupported compression for {}", name)
+            }
+            DebError::InvalidCompressionLevel(level) => {
+                write!(f, "invalid compression level {}", level)
+            }
+            DebError::MissingMember(name) => write!(f, "deb package is missing {}", name),
+            DebError::MalformedAr(reason) => write!(f, "malformed ar archive: {}", reason),
+            DebError::ArHeaderOverflow(reason) => write!(f, "can't write ar header: {}", reason),
+            DebError::UnsupportedFormat(version) => {
+                write!(f, "unsupported package format version {}", version)
+            }
+            DebError::InvalidMember {
+                member,
+                line,
+                reason,
+            } => write!(f, "invalid {} on line {}: {}", member, line, reason),
+            DebError::LimitExceeded(limit) => write!(f, "package exceeds the {}", limit),
+            DebError::UnsafePath(path) => write!(f, "unsafe path {}", path.display()),
+            DebError::UnsupportedEntry(path) => {
+                write!(f, "unsupported entry type for {}", path.display())
+            }
+            DebError::FileChanged(path) => {
+                write!(f, "{} changed after it was read", path.display())
+            }
+            DebError::MissingMd5sums => write!(f, "deb package has no md5sums"),
+            DebError::InvalidSourceDateEpoch(value) => {
+                write!(f, "invalid SOURCE_DATE_EPOCH `{}`", value)
+            }
+            DebError::InvalidOpenPgp(reason) => write!(f, "invalid OpenPGP data: {}", reason),
+            DebError::UnknownSigningKey(key) => write!(f, "signed by unknown key {}", key),
+            DebError::BadSignature => write!(f, "signature doesn't match"),
+            DebError::RevokedKey(key) => write!(f, "signed by revoked key {}", key),
+            DebError::ExpiredKey(key) => write!(f, "signed by expired key {}", key),
+            DebError::ExpiredSignature => write!(f, "signature has expired"),
+        }
+    }
+}
+
+impl std::error::Error for DebError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DebError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DebError {
+    fn from(error: std::io::Error) -> Self {
+        // DebErrors passed through std::io interfaces, such as Read
+        // implementations, are unwrapped rather than nested
+        if error.get_ref().is_some_and(|inner| inner.is::<DebError>()) {
+            return *error.into_inner().unwrap().downcast::<DebError>().unwrap();
+        }
+        DebError::Io(error)
+    }
+}
+
+impl From<DebError> for std::io::Error {
+    fn from(error: DebError) -> Self {
+        match error {
+            DebError::Io(error) => error,
+            error => std::io::Error::other(error),
+        }
+    }
+}
@@ -30,8 +30,28 @@
 
 #[allow(unused)]
 pub mod binary;
+mod compression;
+mod deb822;
+mod error;
+mod openpgp;
+mod relation;
+pub mod repository;
 mod shared;
+mod shlibs;
+mod symbols;
+mod templates;
 #[cfg(test)]
 mod test;
+mod triggers;
+mod version;
 
+pub use deb822::*;
+pub use error::*;
+pub use openpgp::*;
+pub use relation::*;
 pub use shared::*;
+pub use shlibs::*;
+pub use symbols::*;
+pub use templates::*;
+pub use triggers::*;
+pub use version::*;
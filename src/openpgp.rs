@@ -0,0 +1,1140 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// A minimal implementation of OpenPGP (RFC 4880), covering what's needed to
+// sign and verify repositories and packages: Ed25519 keys, and detached and
+// cleartext signatures
+
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::error::DebError;
+
+// The EdDSA public key algorithm, and the object identifier of its Ed25519 curve
+const ALGORITHM_EDDSA: u8 = 22;
+const CURVE_ED25519: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+// Hash algorithms
+const HASH_SHA256: u8 = 8;
+const HASH_SHA512: u8 = 10;
+
+// Packet tags
+const TAG_SIGNATURE: u8 = 2;
+const TAG_SECRET_KEY: u8 = 5;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_SECRET_SUBKEY: u8 = 7;
+const TAG_USER_ID: u8 = 13;
+const TAG_PUBLIC_SUBKEY: u8 = 14;
+const TAG_USER_ATTRIBUTE: u8 = 17;
+
+// Signature types
+const SIGNATURE_BINARY: u8 = 0x00;
+const SIGNATURE_TEXT: u8 = 0x01;
+const SIGNATURE_GENERIC_CERTIFICATION: u8 = 0x10;
+const SIGNATURE_POSITIVE_CERTIFICATION: u8 = 0x13;
+const SIGNATURE_SUBKEY_BINDING: u8 = 0x18;
+const SIGNATURE_PRIMARY_KEY_BINDING: u8 = 0x19;
+const SIGNATURE_DIRECT_KEY: u8 = 0x1f;
+const SIGNATURE_KEY_REVOCATION: u8 = 0x20;
+const SIGNATURE_SUBKEY_REVOCATION: u8 = 0x28;
+
+// Signature subpacket types
+const SUBPACKET_CREATION_TIME: u8 = 2;
+const SUBPACKET_SIGNATURE_EXPIRATION: u8 = 3;
+const SUBPACKET_KEY_EXPIRATION: u8 = 9;
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_KEY_FLAGS: u8 = 27;
+const SUBPACKET_EMBEDDED_SIGNATURE: u8 = 32;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+// The key flag marking keys which can sign data
+const KEY_FLAG_SIGN: u8 = 0x02;
+
+/// An OpenPGP public key, used to verify signatures.
+///
+/// Only Ed25519 keys are supported, such as those generated by
+/// `gpg --quick-generate-key <user id> ed25519`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebPublicKey {
+    key: VerifyingKey,
+    created: u32,          // Seconds since the Unix epoch
+    fingerprint: [u8; 20], // The SHA-1 fingerprint of the key
+    user_ids: Vec<String>, // The user IDs of the key, or of its primary key for subkeys
+    expires: Option<u64>,  // Seconds since the Unix epoch, if the key expires
+    revoked: bool,         // Whether the key, or its primary key, has been revoked
+}
+
+impl DebPublicKey {
+    /// Returns the key's fingerprint in uppercase hexadecimal.
+    pub fn fingerprint(&self) -> String {
+        hex(&self.fingerprint)
+    }
+
+    /// Returns the key's long ID, the last 16 digits of its fingerprint.
+    pub fn key_id(&self) -> String {
+        hex(&self.fingerprint[12..])
+    }
+
+    /// Returns the key's user IDs, such as `Example <example@example.com>`.
+    pub fn user_ids(&self) -> &Vec<String> {
+        &self.user_ids
+    }
+
+    /// Returns the time the key was created, in seconds since the Unix epoch.
+    pub fn created(&self) -> u64 {
+        self.created as u64
+    }
+
+    /// Returns the time the key expires, in seconds since the Unix epoch, if it does.
+    ///
+    /// A subkey expires no later than its primary key.
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    /// Returns whether the key, or its primary key, has been revoked.
+    pub fn revoked(&self) -> bool {
+        self.revoked
+    }
+
+    // Returns the body of the key's public key packet
+    fn packet_body(&self) -> Vec<u8> {
+        public_key_body(self.created, self.key.as_bytes())
+    }
+}
+
+/// An OpenPGP secret key, used to sign repositories and packages.
+///
+/// Keys are loaded from files rather than through gpg-agent, so signing works
+/// offline. Only unencrypted Ed25519 keys are supported, such as those exported
+/// with `gpg --export-secret-keys` from a key without a passphrase.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> Result<(), deb_rust::DebError> {
+/// use deb_rust::{DebKeyring, DebSigningKey};
+///
+/// let key = DebSigningKey::from_path("signing-key.asc")?;
+/// let signature = key.sign(b"example");
+///
+/// let keyring = DebKeyring::new().with_key(key.public_key().clone());
+/// let signer = keyring.verify(b"example", signature.as_bytes())?;
+/// println!("signed by {}", signer.fingerprint());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebSigningKey {
+    secret: SigningKey,
+    public: DebPublicKey,
+}
+
+impl DebSigningKey {
+    /// Creates a DebSigningKey from the 32 byte Ed25519 secret key `seed`.
+    ///
+    /// `created` is the time the key was created, in seconds since the Unix
+    /// epoch. Together with `seed`, it determines the key's fingerprint.
+    pub fn from_seed(seed: [u8; 32], user_id: &str, created: u64) -> Self {
+        let secret = SigningKey::from_bytes(&seed);
+        let created = created as u32;
+        let key = secret.verifying_key();
+        Self {
+            public: DebPublicKey {
+                fingerprint: fingerprint(&public_key_body(created, key.as_bytes())),
+                key,
+                created,
+                user_ids: vec![user_id.to_string()],
+                expires: None,
+                revoked: false,
+            },
+            secret,
+        }
+    }
+
+    /// Reads a secret key from `buf`, in either ASCII armored or binary form.
+    ///
+    /// If the key has several Ed25519 keys, such as a primary key and a signing
+    /// subkey, the last one is used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `buf` doesn't contain an unencrypted
+    /// Ed25519 secret key.
+    pub fn from_buf(buf: &[u8]) -> Result<Self, DebError> {
+        let packets = dearmor(buf)?;
+        let mut output: Option<Self> = None;
+        let mut user_ids: Vec<String> = Vec::new();
+        for (tag, body) in parse_packets(&packets)? {
+            match tag {
+                TAG_SECRET_KEY | TAG_SECRET_SUBKEY => {
+                    if let Some(key) = parse_secret_key(body)? {
+                        output = Some(key);
+                    }
+                }
+                TAG_USER_ID => user_ids.push(String::from_utf8_lossy(body).into_owned()),
+                _ => {}
+            }
+        }
+        match output {
+            Some(mut key) => {
+                key.public.user_ids = user_ids;
+                Ok(key)
+            }
+            None => Err(invalid("no Ed25519 secret key found")),
+        }
+    }
+
+    /// Reads a secret key from the file at `path`, in either ASCII armored or binary form.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, DebError> {
+        Self::from_buf(&fs::read(path)?)
+    }
+
+    /// Returns the key's public key.
+    pub fn public_key(&self) -> &DebPublicKey {
+        &self.public
+    }
+
+    /// Creates an ASCII armored detached signature of `data`, such as a
+    /// repository's `Release.gpg`.
+    pub fn sign(&self, data: &[u8]) -> String {
+        armor("SIGNATURE", &self.sign_binary(data))
+    }
+
+    /// Creates a detached signature of `data` in binary form.
+    pub fn sign_binary(&self, data: &[u8]) -> Vec<u8> {
+        self.signature_packet(SIGNATURE_BINARY, &[data], &[])
+    }
+
+    /// Creates a cleartext signed copy of `text`, such as a repository's `InRelease`.
+    pub fn clearsign(&self, text: &str) -> String {
+        let mut output = String::from("-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\n");
+        for line in text.lines() {
+            // Lines that could be mistaken for armor are dash-escaped
+            if line.starts_with('-') {
+                output.push_str("- ");
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+        let canonical = canonical_text(text.lines());
+        output.push_str(&armor(
+            "SIGNATURE",
+            &self.signature_packet(SIGNATURE_TEXT, &[canonical.as_bytes()], &[]),
+        ));
+        output
+    }
+
+    /// Exports the secret key in ASCII armored form, which can be read back by
+    /// `from_buf()` or imported into gpg.
+    pub fn armored_secret_key(&self) -> String {
+        let mut body = self.public.packet_body();
+        body.push(0); // The secret key isn't encrypted
+        let secret = mpi(self.secret.as_bytes());
+        let checksum = secret
+            .iter()
+            .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+        body.extend(secret);
+        body.extend(checksum.to_be_bytes());
+        armor("PRIVATE KEY BLOCK", &self.certificate(TAG_SECRET_KEY, body))
+    }
+
+    /// Exports the public key in ASCII armored form, which can be read by
+    /// DebKeyring or imported into gpg and APT.
+    pub fn armored_public_key(&self) -> String {
+        armor(
+            "PUBLIC KEY BLOCK",
+            &self.certificate(TAG_PUBLIC_KEY, self.public.packet_body()),
+        )
+    }
+
+    // Creates the packets of a certificate: the key packet, and each user ID
+    // followed by its self-signature
+    fn certificate(&self, tag: u8, key_body: Vec<u8>) -> Vec<u8> {
+        let public = key_prefix(&self.public.packet_body());
+        let mut output = packet(tag, &key_body);
+        for user_id in &self.public.user_ids {
+            output.extend(packet(TAG_USER_ID, user_id.as_bytes()));
+            output.extend(self.signature_packet(
+                SIGNATURE_POSITIVE_CERTIFICATION,
+                &[&public, &user_id_prefix(user_id.as_bytes())],
+                // The key can certify and sign
+                &[SUBPACKET_KEY_FLAGS, 0x03],
+            ));
+        }
+        output
+    }
+
+    // Creates a signature packet of `sig_type` over `data`, with `subpacket` added
+    // to the hashed subpackets if it isn't empty
+    fn signature_packet(&self, sig_type: u8, data: &[&[u8]], subpacket: &[u8]) -> Vec<u8> {
+        let created = chrono::Utc::now().timestamp() as u32;
+        let mut subpackets: Vec<u8> = Vec::new();
+        subpackets.extend([5, SUBPACKET_CREATION_TIME]);
+        subpackets.extend(created.to_be_bytes());
+        subpackets.extend([22, SUBPACKET_ISSUER_FINGERPRINT, 4]);
+        subpackets.extend(self.public.fingerprint);
+        if !subpacket.is_empty() {
+            subpackets.push(subpacket.len() as u8);
+            subpackets.extend(subpacket);
+        }
+
+        let mut hashed = vec![4, sig_type, ALGORITHM_EDDSA, HASH_SHA256];
+        hashed.extend((subpackets.len() as u16).to_be_bytes());
+        hashed.extend(subpackets);
+        let digest =
+            signature_digest(HASH_SHA256, data, &hashed).expect("SHA-256 is always supported");
+        let signature = self.secret.sign(&digest).to_bytes();
+
+        let mut body = hashed;
+        body.extend([0, 10, 9, SUBPACKET_ISSUER]);
+        body.extend(&self.public.fingerprint[12..]);
+        body.extend(&digest[..2]);
+        body.extend(mpi(&signature[..32]));
+        body.extend(mpi(&signature[32..]));
+        packet(TAG_SIGNATURE, &body)
+    }
+}
+
+/// A set of trusted OpenPGP public keys, used to verify signatures.
+///
+/// Like gpgv, every key in the keyring is trusted as-is, but signatures made
+/// by revoked or expired keys, and expired signatures, are rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebKeyring {
+    keys: Vec<DebPublicKey>,
+}
+
+impl DebKeyring {
+    /// Creates an empty DebKeyring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads every Ed25519 key and subkey which can sign from `buf`, in either
+    /// ASCII armored or binary form.
+    ///
+    /// User IDs and subkeys are only kept if their self-signatures verify, and
+    /// subkeys must be bound to their primary key as signing keys. Revocations
+    /// and expiry times are read from the self-signatures. Certificates whose
+    /// primary key isn't an Ed25519 key are skipped, since their self-signatures
+    /// can't be checked.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `buf` isn't valid OpenPGP data.
+    pub fn from_buf(buf: &[u8]) -> Result<Self, DebError> {
+        let mut output = Self::new();
+        let data = dearmor(buf)?;
+        let packets = parse_packets(&data)?;
+        // Each certificate starts with a primary key packet
+        let mut starts: Vec<usize> = packets
+            .iter()
+            .enumerate()
+            .filter(|(_, (tag, _))| *tag == TAG_PUBLIC_KEY || *tag == TAG_SECRET_KEY)
+            .map(|(index, _)| index)
+            .collect();
+        starts.push(packets.len());
+        for range in starts.windows(2) {
+            output
+                .keys
+                .extend(parse_certificate(&packets[range[0]..range[1]])?);
+        }
+        Ok(output)
+    }
+
+    /// Reads every key from the file at `path`, such as a keyring exported with
+    /// `gpg --export`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, DebError> {
+        Self::from_buf(&fs::read(path)?)
+    }
+
+    /// Adds `key` to the keyring.
+    pub fn with_key(mut self, key: DebPublicKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Returns the keyring's keys.
+    pub fn keys(&self) -> &Vec<DebPublicKey> {
+        &self.keys
+    }
+
+    /// Verifies a detached `signature` of `data`, in either ASCII armored or
+    /// binary form, returning the key which made it.
+    ///
+    /// If `signature` holds several signatures, such as when a repository is
+    /// signed by more than one key, one made by a key in the keyring is enough.
+    ///
+    /// Text signatures, such as those made with `gpg --textmode`, are checked
+    /// over `data` with trailing whitespace removed and CRLF line endings.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the signature is malformed, if it
+    /// wasn't made by a key in the keyring, if it doesn't match `data`, if the
+    /// key has been revoked or has expired, or if the signature has expired.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<&DebPublicKey, DebError> {
+        self.verify_packets(&dearmor(signature)?, data)
+    }
+
+    /// Verifies a cleartext signed message, such as a repository's `InRelease`,
+    /// returning the signed text and the key which signed it. As with
+    /// `verify()`, one signature by a key in the keyring is enough.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a cleartext signed
+    /// message, if it wasn't signed by a key in the keyring, if the signature
+    /// doesn't match the text, if the key has been revoked or has expired, or if
+    /// the signature has expired.
+    pub fn verify_clearsigned(&self, input: &str) -> Result<(String, &DebPublicKey), DebError> {
+        let mut lines = input.lines();
+        if lines.next().map(str::trim_end) != Some("-----BEGIN PGP SIGNED MESSAGE-----") {
+            return Err(invalid("missing cleartext header"));
+        }
+        // Armor headers, such as `Hash: SHA256`, end at the first blank line
+        for line in lines.by_ref() {
+            if line.trim().is_empty() {
+                break;
+            }
+        }
+        let mut text: Vec<&str> = Vec::new();
+        let mut signature = String::new();
+        for line in lines.by_ref() {
+            if line.trim_end() == "-----BEGIN PGP SIGNATURE-----" {
+                signature.push_str(line);
+                signature.push('\n');
+                break;
+            }
+            text.push(line.strip_prefix("- ").unwrap_or(line));
+        }
+        for line in lines {
+            signature.push_str(line);
+            signature.push('\n');
+        }
+
+        let canonical = canonical_text(text.iter().copied());
+        let key = self.verify_packets(&dearmor(signature.as_bytes())?, canonical.as_bytes())?;
+        let mut output = text.join("\n");
+        output.push('\n');
+        Ok((output, key))
+    }
+
+    // Verifies the signature packets in `packets` over `data`, returning the
+    // first trusted key which made one of them. If none verify, the most
+    // specific error is returned: a bad signature from a trusted key, then a
+    // revoked or expired key or signature, then an unknown key, then an
+    // unsupported or malformed signature.
+    fn verify_packets(&self, packets: &[u8], data: &[u8]) -> Result<&DebPublicKey, DebError> {
+        let rank = |error: &DebError| match error {
+            DebError::BadSignature => 3,
+            DebError::RevokedKey(_) | DebError::ExpiredKey(_) | DebError::ExpiredSignature => 2,
+            DebError::UnknownSigningKey(_) => 1,
+            _ => 0,
+        };
+        let mut error = invalid("no signature found");
+        for (tag, body) in parse_packets(packets)? {
+            if tag != TAG_SIGNATURE {
+                continue;
+            }
+            match self.verify_signature(body, data) {
+                Ok(key) => return Ok(key),
+                Err(new) if rank(&new) >= rank(&error) => error = new,
+                Err(_) => {}
+            }
+        }
+        Err(error)
+    }
+
+    // Verifies a single signature packet over `data`. Text signatures are
+    // checked over the canonical form of `data`.
+    fn verify_signature(&self, body: &[u8], data: &[u8]) -> Result<&DebPublicKey, DebError> {
+        let signature = parse_signature(body)?;
+        let text;
+        let data = match signature.sig_type {
+            SIGNATURE_BINARY => data,
+            SIGNATURE_TEXT => {
+                text = text_mode(data);
+                &text
+            }
+            _ => return Err(invalid("not a document signature")),
+        };
+
+        let key = match self
+            .keys
+            .iter()
+            .find(|key| match signature.issuer_fingerprint {
+                Some(fingerprint) => key.fingerprint == fingerprint,
+                None => signature.issuer == Some(key.fingerprint[12..].try_into().unwrap()),
+            }) {
+            Some(key) => key,
+            None => {
+                return Err(DebError::UnknownSigningKey(
+                    signature
+                        .issuer_fingerprint
+                        .map(|fingerprint| hex(&fingerprint))
+                        .or(signature.issuer.map(|issuer| hex(&issuer)))
+                        .unwrap_or_default(),
+                ))
+            }
+        };
+
+        let digest = signature_digest(signature.hash_algorithm, &[data], &signature.hashed)?;
+        if digest[..2] != signature.digest_prefix
+            || key
+                .key
+                .verify(&digest, &Signature::from_bytes(&signature.signature))
+                .is_err()
+        {
+            return Err(DebError::BadSignature);
+        }
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        if key.revoked {
+            return Err(DebError::RevokedKey(key.fingerprint()));
+        }
+        if key.expires.is_some_and(|expires| now >= expires) {
+            return Err(DebError::ExpiredKey(key.fingerprint()));
+        }
+        if expiry(signature.created, signature.expires).is_some_and(|expires| now >= expires) {
+            return Err(DebError::ExpiredSignature);
+        }
+        Ok(key)
+    }
+}
+
+// Where the packets of a certificate currently being read belong
+enum Section<'a> {
+    Primary,
+    UserId(&'a [u8]),
+    Subkey(usize),
+    Other, // Unsupported subkeys and user attributes
+}
+
+// A subkey while its certificate is read
+struct Subkey {
+    key: DebPublicKey,
+    binding: Option<SignaturePacket>, // The newest valid binding signature
+    back_signed: bool,                // Whether the binding's back signature is valid
+}
+
+// Parses the packets of one certificate, from its primary key up to the next
+// primary key, returning the keys in it which can sign. Signatures which fail
+// to parse or verify are ignored, like gpg does.
+fn parse_certificate(packets: &[(u8, &[u8])]) -> Result<Vec<DebPublicKey>, DebError> {
+    let mut primary = match parse_public_key(packets[0].1)? {
+        Some((key, _)) => key,
+        None => return Ok(Vec::new()),
+    };
+    let primary_prefix = key_prefix(&primary.packet_body());
+    let mut self_signature: Option<SignaturePacket> = None; // The newest one
+    let mut subkeys: Vec<Subkey> = Vec::new();
+    let mut section = Section::Primary;
+    for (tag, body) in &packets[1..] {
+        let signature = match *tag {
+            TAG_USER_ID => {
+                section = Section::UserId(body);
+                continue;
+            }
+            TAG_USER_ATTRIBUTE => {
+                section = Section::Other;
+                continue;
+            }
+            TAG_PUBLIC_SUBKEY | TAG_SECRET_SUBKEY => {
+                section = match parse_public_key(body)? {
+                    Some((key, _)) => {
+                        subkeys.push(Subkey {
+                            key,
+                            binding: None,
+                            back_signed: false,
+                        });
+                        Section::Subkey(subkeys.len() - 1)
+                    }
+                    None => Section::Other,
+                };
+                continue;
+            }
+            TAG_SIGNATURE => match parse_signature(body) {
+                Ok(signature) => signature,
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+
+        match section {
+            Section::Primary => match signature.sig_type {
+                SIGNATURE_KEY_REVOCATION if signed_by(&primary, &signature, &[&primary_prefix]) => {
+                    primary.revoked = true;
+                }
+                SIGNATURE_DIRECT_KEY if signed_by(&primary, &signature, &[&primary_prefix]) => {
+                    newest(&mut self_signature, signature);
+                }
+                _ => {}
+            },
+            Section::UserId(user_id) => {
+                let data: [&[u8]; 2] = [&primary_prefix, &user_id_prefix(user_id)];
+                if (SIGNATURE_GENERIC_CERTIFICATION..=SIGNATURE_POSITIVE_CERTIFICATION)
+                    .contains(&signature.sig_type)
+                    && signed_by(&primary, &signature, &data)
+                {
+                    let user_id = String::from_utf8_lossy(user_id).into_owned();
+                    if !primary.user_ids.contains(&user_id) {
+                        primary.user_ids.push(user_id);
+                    }
+                    newest(&mut self_signature, signature);
+                }
+            }
+            Section::Subkey(index) => {
+                let subkey = &mut subkeys[index];
+                let subkey_prefix = key_prefix(&subkey.key.packet_body());
+                let data: [&[u8]; 2] = [&primary_prefix, &subkey_prefix];
+                if !signed_by(&primary, &signature, &data) {
+                    continue;
+                }
+                match signature.sig_type {
+                    SIGNATURE_SUBKEY_REVOCATION => subkey.key.revoked = true,
+                    SIGNATURE_SUBKEY_BINDING
+                        if subkey
+                            .binding
+                            .as_ref()
+                            .is_none_or(|binding| signature.created >= binding.created) =>
+                    {
+                        // Signing subkeys must sign the primary key back, so
+                        // others can't claim them
+                        subkey.back_signed = signature
+                            .embedded
+                            .as_deref()
+                            .and_then(|embedded| parse_signature(embedded).ok())
+                            .is_some_and(|back| {
+                                back.sig_type == SIGNATURE_PRIMARY_KEY_BINDING
+                                    && signed_by(&subkey.key, &back, &data)
+                            });
+                        subkey.binding = Some(signature);
+                    }
+                    _ => {}
+                }
+            }
+            Section::Other => {}
+        }
+    }
+
+    // Like gpg, keys without a valid self-signature aren't used
+    let self_signature = match self_signature {
+        Some(signature) => signature,
+        None => return Ok(Vec::new()),
+    };
+    primary.expires = expiry(primary.created, self_signature.key_expires);
+    let mut output = Vec::new();
+    for subkey in subkeys {
+        let binding = match subkey.binding {
+            Some(binding) if subkey.back_signed => binding,
+            _ => continue,
+        };
+        if !can_sign(&binding) {
+            continue;
+        }
+        let mut key = subkey.key;
+        key.user_ids = primary.user_ids.clone();
+        key.revoked |= primary.revoked;
+        key.expires = match (expiry(key.created, binding.key_expires), primary.expires) {
+            (Some(expires), Some(primary)) => Some(expires.min(primary)),
+            (expires, primary) => expires.or(primary),
+        };
+        output.push(key);
+    }
+    // Primary keys without key flags are assumed to sign
+    if self_signature.key_flags.is_none() || can_sign(&self_signature) {
+        output.insert(0, primary);
+    }
+    Ok(output)
+}
+
+// Returns whether the key flags of a self-signature allow signing data
+fn can_sign(signature: &SignaturePacket) -> bool {
+    signature
+        .key_flags
+        .is_some_and(|flags| flags & KEY_FLAG_SIGN != 0)
+}
+
+// Replaces `current` with `signature` unless `current` is newer
+fn newest(current: &mut Option<SignaturePacket>, signature: SignaturePacket) {
+    if current
+        .as_ref()
+        .is_none_or(|current| signature.created >= current.created)
+    {
+        *current = Some(signature);
+    }
+}
+
+// Returns whether `signature` over `data` was made by `key`
+fn signed_by(key: &DebPublicKey, signature: &SignaturePacket, data: &[&[u8]]) -> bool {
+    match signature_digest(signature.hash_algorithm, data, &signature.hashed) {
+        Ok(digest) => {
+            digest[..2] == signature.digest_prefix
+                && key
+                    .key
+                    .verify(&digest, &Signature::from_bytes(&signature.signature))
+                    .is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+// Returns the time something created at `created` expires, in seconds since the
+// Unix epoch, given its lifetime in `seconds`. A lifetime of 0 never expires.
+fn expiry(created: u32, seconds: u32) -> Option<u64> {
+    match seconds {
+        0 => None,
+        seconds => Some(created as u64 + seconds as u64),
+    }
+}
+
+// The parts of a signature packet needed to verify it
+struct SignaturePacket {
+    sig_type: u8,
+    hash_algorithm: u8,
+    hashed: Vec<u8>, // The packet from its version to the end of the hashed subpackets
+    issuer: Option<[u8; 8]>,
+    issuer_fingerprint: Option<[u8; 20]>,
+    created: u32,
+    expires: u32,              // Seconds after `created`, or 0 if it doesn't expire
+    key_expires: u32,          // Seconds after the key's creation, or 0 if it doesn't expire
+    key_flags: Option<u8>,     // The first byte of the key flags
+    embedded: Option<Vec<u8>>, // An embedded signature, such as a back signature
+    digest_prefix: [u8; 2],
+    signature: [u8; 64],
+}
+
+// Reads the fields of a packet in order
+struct PacketReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PacketReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], DebError> {
+        if self.data.len() < length {
+            return Err(invalid("packet is truncated"));
+        }
+        let (output, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(output)
+    }
+
+    fn u8(&mut self) -> Result<u8, DebError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DebError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, DebError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    // Reads a multiprecision integer, left padded with zeros to `length` bytes
+    fn mpi(&mut self, length: usize) -> Result<Vec<u8>, DebError> {
+        let bits = self.u16()? as usize;
+        let bytes = self.take(bits.div_ceil(8))?;
+        if bytes.len() > length {
+            return Err(invalid("integer is too large"));
+        }
+        let mut output = vec![0; length - bytes.len()];
+        output.extend(bytes);
+        Ok(output)
+    }
+}
+
+// Parses the public part of a key packet, returning None if the key isn't an
+// Ed25519 key. The reader is returned so secret keys can continue parsing.
+fn parse_public_key(body: &[u8]) -> Result<Option<(DebPublicKey, PacketReader<'_>)>, DebError> {
+    let mut reader = PacketReader { data: body };
+    if reader.u8()? != 4 {
+        return Ok(None);
+    }
+    let created = reader.u32()?;
+    if reader.u8()? != ALGORITHM_EDDSA {
+        return Ok(None);
+    }
+    let curve_length = reader.u8()? as usize;
+    if reader.take(curve_length)? != CURVE_ED25519 {
+        return Ok(None);
+    }
+    // Points are prefixed with 0x40 to mark them as compressed
+    let point = reader.mpi(33)?;
+    if point[0] != 0x40 {
+        return Err(invalid("invalid Ed25519 public key"));
+    }
+    let key = match VerifyingKey::from_bytes(point[1..].try_into().unwrap()) {
+        Ok(key) => key,
+        Err(_) => return Err(invalid("invalid Ed25519 public key")),
+    };
+    let key = DebPublicKey {
+        key,
+        created,
+        fingerprint: fingerprint(&public_key_body(created, key.as_bytes())),
+        user_ids: Vec::new(),
+        expires: None,
+        revoked: false,
+    };
+    Ok(Some((key, reader)))
+}
+
+// Parses a secret key packet, returning None if the key isn't an Ed25519 key
+fn parse_secret_key(body: &[u8]) -> Result<Option<DebSigningKey>, DebError> {
+    let (public, mut reader) = match parse_public_key(body)? {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    if reader.u8()? != 0 {
+        return Err(invalid("encrypted secret keys aren't supported"));
+    }
+    let start = reader.data;
+    let seed: [u8; 32] = reader.mpi(32)?.try_into().unwrap();
+    let secret_length = start.len() - reader.data.len();
+    let checksum = start[..secret_length]
+        .iter()
+        .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+    if reader.u16()? != checksum {
+        return Err(invalid("secret key checksum doesn't match"));
+    }
+
+    let secret = SigningKey::from_bytes(&seed);
+    if secret.verifying_key() != public.key {
+        return Err(invalid("secret key doesn't match its public key"));
+    }
+    Ok(Some(DebSigningKey { secret, public }))
+}
+
+// Parses a version 4 EdDSA signature packet
+fn parse_signature(body: &[u8]) -> Result<SignaturePacket, DebError> {
+    let mut reader = PacketReader { data: body };
+    if reader.u8()? != 4 {
+        return Err(invalid("only version 4 signatures are supported"));
+    }
+    let sig_type = reader.u8()?;
+    if reader.u8()? != ALGORITHM_EDDSA {
+        return Err(invalid("only EdDSA signatures are supported"));
+    }
+    let hash_algorithm = reader.u8()?;
+    let hashed_length = reader.u16()? as usize;
+    let hashed_subpackets = reader.take(hashed_length)?;
+    let hashed = body[..6 + hashed_length].to_vec();
+    let unhashed_length = reader.u16()? as usize;
+    let unhashed_subpackets = reader.take(unhashed_length)?;
+
+    let mut output = SignaturePacket {
+        sig_type,
+        hash_algorithm,
+        hashed,
+        issuer: None,
+        issuer_fingerprint: None,
+        created: 0,
+        expires: 0,
+        key_expires: 0,
+        key_flags: None,
+        embedded: None,
+        digest_prefix: reader.take(2)?.try_into().unwrap(),
+        signature: [0; 64],
+    };
+    output.signature[..32].copy_from_slice(&reader.mpi(32)?);
+    output.signature[32..].copy_from_slice(&reader.mpi(32)?);
+
+    // Only the issuer and embedded signatures, which are checked by other means,
+    // are trusted from the unhashed subpackets
+    for (subpackets, hashed) in [(hashed_subpackets, true), (unhashed_subpackets, false)] {
+        let mut reader = PacketReader { data: subpackets };
+        while !reader.data.is_empty() {
+            let length = match reader.u8()? {
+                length @ 0..=191 => length as usize,
+                first @ 192..=254 => ((first as usize - 192) << 8) + reader.u8()? as usize + 192,
+                255 => reader.u32()? as usize,
+            };
+            if length == 0 {
+                return Err(invalid("empty signature subpacket"));
+            }
+            let data = reader.take(length)?;
+            // The top bit marks subpackets as critical
+            match data[0] & 0x7f {
+                SUBPACKET_ISSUER if data.len() == 9 => {
+                    output.issuer = Some(data[1..].try_into().unwrap());
+                }
+                SUBPACKET_ISSUER_FINGERPRINT if data.len() == 22 && data[1] == 4 => {
+                    output.issuer_fingerprint = Some(data[2..].try_into().unwrap());
+                }
+                SUBPACKET_EMBEDDED_SIGNATURE => output.embedded = Some(data[1..].to_vec()),
+                SUBPACKET_CREATION_TIME if hashed && data.len() == 5 => {
+                    output.created = u32::from_be_bytes(data[1..].try_into().unwrap());
+                }
+                SUBPACKET_SIGNATURE_EXPIRATION if hashed && data.len() == 5 => {
+                    output.expires = u32::from_be_bytes(data[1..].try_into().unwrap());
+                }
+                SUBPACKET_KEY_EXPIRATION if hashed && data.len() == 5 => {
+                    output.key_expires = u32::from_be_bytes(data[1..].try_into().unwrap());
+                }
+                SUBPACKET_KEY_FLAGS if hashed && data.len() >= 2 => {
+                    output.key_flags = Some(data[1]);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(output)
+}
+
+// Splits OpenPGP data into the tags and bodies of its packets
+fn parse_packets(input: &[u8]) -> Result<Vec<(u8, &[u8])>, DebError> {
+    let mut output = Vec::new();
+    let mut reader = PacketReader { data: input };
+    while !reader.data.is_empty() {
+        let header = reader.u8()?;
+        if header & 0x80 == 0 {
+            return Err(invalid("invalid packet header"));
+        }
+        let (tag, length) = match header & 0x40 != 0 {
+            // New format headers
+            true => {
+                let length = match reader.u8()? {
+                    length @ 0..=191 => length as usize,
+                    first @ 192..=223 => {
+                        ((first as usize - 192) << 8) + reader.u8()? as usize + 192
+                    }
+                    255 => reader.u32()? as usize,
+                    _ => return Err(invalid("partial body lengths aren't supported")),
+                };
+                (header & 0x3f, length)
+            }
+            // Old format headers
+            false => {
+                let length = match header & 0x03 {
+                    0 => reader.u8()? as usize,
+                    1 => reader.u16()? as usize,
+                    2 => reader.u32()? as usize,
+                    _ => reader.data.len(),
+                };
+                ((header >> 2) & 0x0f, length)
+            }
+        };
+        output.push((tag, reader.take(length)?));
+    }
+    Ok(output)
+}
+
+// Creates a packet with a new format header
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut output = vec![0xc0 | tag];
+    match body.len() {
+        length @ 0..=191 => output.push(length as u8),
+        length @ 192..=8383 => {
+            output.push((((length - 192) >> 8) + 192) as u8);
+            output.push(((length - 192) & 0xff) as u8);
+        }
+        length => {
+            output.push(255);
+            output.extend((length as u32).to_be_bytes());
+        }
+    }
+    output.extend(body);
+    output
+}
+
+// Encodes `bytes` as a multiprecision integer, without leading zeros
+fn mpi(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    let bits = match bytes.first() {
+        Some(first) => (bytes.len() - 1) * 8 + (8 - first.leading_zeros() as usize),
+        None => 0,
+    };
+    let mut output = (bits as u16).to_be_bytes().to_vec();
+    output.extend(bytes);
+    output
+}
+
+// Creates the body of an Ed25519 public key packet
+fn public_key_body(created: u32, key: &[u8; 32]) -> Vec<u8> {
+    let mut output = vec![4];
+    output.extend(created.to_be_bytes());
+    output.push(ALGORITHM_EDDSA);
+    output.push(CURVE_ED25519.len() as u8);
+    output.extend(CURVE_ED25519);
+    let mut point = vec![0x40];
+    point.extend(key);
+    output.extend(mpi(&point));
+    output
+}
+
+// Creates the prefix a public key packet body is hashed with in key signatures
+fn key_prefix(body: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x99];
+    output.extend((body.len() as u16).to_be_bytes());
+    output.extend(body);
+    output
+}
+
+// Creates the prefix a user ID is hashed with in certifications
+fn user_id_prefix(user_id: &[u8]) -> Vec<u8> {
+    let mut output = vec![0xb4];
+    output.extend((user_id.len() as u32).to_be_bytes());
+    output.extend(user_id);
+    output
+}
+
+// Calculates the version 4 fingerprint of a public key packet body
+fn fingerprint(body: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update([0x99]);
+    hasher.update((body.len() as u16).to_be_bytes());
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+// Calculates the digest a version 4 signature signs: the signed data, followed
+// by the hashed part of the signature packet and a trailer
+fn signature_digest(algorithm: u8, data: &[&[u8]], hashed: &[u8]) -> Result<Vec<u8>, DebError> {
+    fn digest<D: Digest>(data: &[&[u8]], hashed: &[u8]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for part in data {
+            hasher.update(part);
+        }
+        hasher.update(hashed);
+        hasher.update([4, 0xff]);
+        hasher.update((hashed.len() as u32).to_be_bytes());
+        hasher.finalize().to_vec()
+    }
+    match algorithm {
+        HASH_SHA256 => Ok(digest::<Sha256>(data, hashed)),
+        HASH_SHA512 => Ok(digest::<Sha512>(data, hashed)),
+        _ => Err(invalid("only SHA-256 and SHA-512 signatures are supported")),
+    }
+}
+
+// Converts lines to the form text signatures are made over: trailing whitespace
+// removed, and lines separated by CRLF
+fn canonical_text<'a, I: Iterator<Item = &'a str>>(lines: I) -> String {
+    lines
+        .map(|line| line.trim_end_matches([' ', '\t', '\r']))
+        .collect::<Vec<&str>>()
+        .join("\r\n")
+}
+
+// Converts data to the form detached text signatures are made over, like
+// canonical_text(), except each line ending becomes CRLF
+fn text_mode(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    for (index, line) in data.split(|byte| *byte == b'\n').enumerate() {
+        if index > 0 {
+            output.extend(b"\r\n");
+        }
+        let end = line
+            .iter()
+            .rposition(|byte| !matches!(byte, b' ' | b'\t' | b'\r'))
+            .map_or(0, |end| end + 1);
+        output.extend(&line[..end]);
+    }
+    output
+}
+
+// Wraps binary OpenPGP data in ASCII armor of `kind`, such as `SIGNATURE`
+fn armor(kind: &str, data: &[u8]) -> String {
+    let encoded = BASE64.encode(data);
+    let mut output = format!("-----BEGIN PGP {}-----\n\n", kind);
+    for line in encoded.as_bytes().chunks(64) {
+        output.push_str(std::str::from_utf8(line).unwrap());
+        output.push('\n');
+    }
+    output.push('=');
+    output.push_str(&BASE64.encode(&crc24(data).to_be_bytes()[1..]));
+    output.push_str(&format!("\n-----END PGP {}-----\n", kind));
+    output
+}
+
+// Removes the ASCII armor from `input`, if it has any
+fn dearmor(input: &[u8]) -> Result<Vec<u8>, DebError> {
+    let text = match std::str::from_utf8(input) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN PGP ") => text,
+        _ => return Ok(input.to_vec()),
+    };
+    let mut lines = text
+        .lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN PGP "));
+    lines.next();
+    // Armor headers end at the first blank line
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut encoded = String::new();
+    let mut checksum: Option<&str> = None;
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with("-----END PGP ") {
+            break;
+        } else if let Some(crc) = line.strip_prefix('=') {
+            checksum = Some(crc);
+        } else {
+            encoded.push_str(line);
+        }
+    }
+    let output = match BASE64.decode(encoded) {
+        Ok(output) => output,
+        Err(_) => return Err(invalid("invalid base64 in armor")),
+    };
+    if let Some(checksum) = checksum {
+        if BASE64.decode(checksum).ok().as_deref() != Some(&crc24(&output).to_be_bytes()[1..]) {
+            return Err(invalid("armor checksum doesn't match"));
+        }
+    }
+    Ok(output)
+}
+
+// Calculates the CRC-24 checksum used by ASCII armor
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xb704ce;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+// Formats bytes as uppercase hexadecimal
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+// Creates an error for malformed or unsupported OpenPGP data
+fn invalid(reason: &str) -> DebError {
+    DebError::InvalidOpenPgp(reason.to_string())
+}
@@ -0,0 +1,420 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+use crate::error::DebError;
+use crate::shared::{DebArchitecture, DebArchitectureWildcard};
+use crate::version::DebVersion;
+
+/// Used for the version constraint of a [DebDependency].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebVersionOperator {
+    /// `<<`
+    Earlier,
+    /// `<=`
+    EarlierOrEqual,
+    /// `=`
+    Equal,
+    /// `>=`
+    LaterOrEqual,
+    /// `>>`
+    Later,
+}
+
+impl DebVersionOperator {
+    /// Converts DebVersionOperator to &str.
+    pub fn as_str(&self) -> &str {
+        match self {
+            DebVersionOperator::Earlier => "<<",
+            DebVersionOperator::EarlierOrEqual => "<=",
+            DebVersionOperator::Equal => "=",
+            DebVersionOperator::LaterOrEqual => ">=",
+            DebVersionOperator::Later => ">>",
+        }
+    }
+
+    /// Converts &str to DebVersionOperator.
+    ///
+    /// The obsolete `<` and `>` operators are read as `<=` and `>=`, as dpkg does.
+    ///
+    /// This function will return an error if the given string isn't a valid operator.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        match input {
+            "<<" => Ok(DebVersionOperator::Earlier),
+            "<=" | "<" => Ok(DebVersionOperator::EarlierOrEqual),
+            "=" => Ok(DebVersionOperator::Equal),
+            ">=" | ">" => Ok(DebVersionOperator::LaterOrEqual),
+            ">>" => Ok(DebVersionOperator::Later),
+            &_ => Err(DebError::InvalidRelation(input.to_string())),
+        }
+    }
+
+    /// Returns whether `version` satisfies this operator applied to `constraint`.
+    ///
+    /// For example, `LaterOrEqual.compare(2.0, 1.0)` is true.
+    pub fn compare(&self, version: &DebVersion, constraint: &DebVersion) -> bool {
+        match self {
+            DebVersionOperator::Earlier => version < constraint,
+            DebVersionOperator::EarlierOrEqual => version <= constraint,
+            DebVersionOperator::Equal => version == constraint,
+            DebVersionOperator::LaterOrEqual => version >= constraint,
+            DebVersionOperator::Later => version > constraint,
+        }
+    }
+}
+
+/// A single package in a relationship field, along with its restrictions.
+///
+/// This represents entries such as `libc6:any (>= 2.34) [amd64] <!nocheck>`.
+/// Read Debian's documentation on [relationship syntax][1] for the meaning of each part.
+///
+/// [1]: https://www.debian.org/doc/debian-policy/ch-relationships.html#syntax-of-relationship-fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebDependency {
+    name: String,                                      // The package's name
+    arch_qualifier: Option<String>,                    // `any`, `native` or an architecture
+    version: Option<(DebVersionOperator, DebVersion)>, // The version constraint
+    architectures: Vec<String>, // Architecture restrictions, may be negated with `!`
+    profiles: Vec<Vec<String>>, // Build profile restriction formulas
+}
+
+impl DebDependency {
+    /// Creates a DebDependency on the package `name`, without any restrictions.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            arch_qualifier: None,
+            version: None,
+            architectures: Vec::new(),
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Parses a DebDependency from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a valid dependency.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        let invalid = || DebError::InvalidRelation(input.trim().to_string());
+        let mut rest = input.trim();
+
+        // Package name, optionally followed by an architecture qualifier
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || "([<:".contains(c))
+            .unwrap_or(rest.len());
+        let mut output = Self::new(&rest[..name_end]);
+        if !is_package_name(&output.name) {
+            return Err(invalid());
+        }
+        rest = &rest[name_end..];
+        if let Some(after) = rest.strip_prefix(':') {
+            let end = after
+                .find(|c: char| c.is_whitespace() || "([<".contains(c))
+                .unwrap_or(after.len());
+            if !is_valid_name(&after[..end]) {
+                return Err(invalid());
+            }
+            output.arch_qualifier = Some(after[..end].to_string());
+            rest = &after[end..];
+        }
+
+        // Restrictions must appear in the order version, architectures, profiles
+        loop {
+            rest = rest.trim_start();
+            let Some(open) = rest.chars().next() else {
+                break;
+            };
+            let close = match open {
+                '(' => ')',
+                '[' => ']',
+                '<' => '>',
+                _ => return Err(invalid()),
+            };
+            let end = rest.find(close).ok_or_else(invalid)?;
+            let inner = rest[1..end].trim();
+            rest = &rest[end + 1..];
+            match open {
+                '(' => {
+                    if output.version.is_some()
+                        || !output.architectures.is_empty()
+                        || !output.profiles.is_empty()
+                    {
+                        return Err(invalid());
+                    }
+                    let op_end = inner
+                        .find(|c: char| !"<=>".contains(c))
+                        .ok_or_else(invalid)?;
+                    let operator = DebVersionOperator::from(&inner[..op_end])?;
+                    let version = DebVersion::from(&inner[op_end..])?;
+                    output.version = Some((operator, version));
+                }
+                '[' => {
+                    if !output.architectures.is_empty() || !output.profiles.is_empty() {
+                        return Err(invalid());
+                    }
+                    let architectures: Vec<String> =
+                        inner.split_whitespace().map(str::to_string).collect();
+                    // dpkg doesn't allow mixing negated and non-negated architectures
+                    let negated = architectures.iter().filter(|a| a.starts_with('!')).count();
+                    if architectures.is_empty()
+                        || (negated != 0 && negated != architectures.len())
+                        || architectures
+                            .iter()
+                            .any(|a| !is_valid_name(a.trim_start_matches('!')))
+                    {
+                        return Err(invalid());
+                    }
+                    output.architectures = architectures;
+                }
+                _ => {
+                    let terms: Vec<String> = inner.split_whitespace().map(str::to_string).collect();
+                    if terms.is_empty()
+                        || terms
+                            .iter()
+                            .any(|t| !is_valid_name(t.trim_start_matches('!')))
+                    {
+                        return Err(invalid());
+                    }
+                    output.profiles.push(terms);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Sets the dependency's architecture qualifier, such as `any` or `native`.
+    pub fn set_arch_qualifier(mut self, qualifier: &str) -> Self {
+        self.arch_qualifier = Some(qualifier.to_string());
+        self
+    }
+
+    /// Resets the dependency's architecture qualifier.
+    pub fn no_arch_qualifier(mut self) -> Self {
+        self.arch_qualifier = None;
+        self
+    }
+
+    /// Sets the dependency's version constraint.
+    pub fn set_version(mut self, operator: DebVersionOperator, version: DebVersion) -> Self {
+        self.version = Some((operator, version));
+        self
+    }
+
+    /// Resets the dependency's version constraint.
+    pub fn no_version(mut self) -> Self {
+        self.version = None;
+        self
+    }
+
+    /// Adds an architecture restriction, such as `amd64` or `!i386`.
+    pub fn with_architecture(mut self, architecture: &str) -> Self {
+        self.architectures.push(architecture.to_string());
+        self
+    }
+
+    /// Resets architecture restrictions.
+    pub fn no_architectures(mut self) -> Self {
+        self.architectures = Vec::new();
+        self
+    }
+
+    /// Adds a build profile restriction formula, such as `vec!["!nocheck"]`.
+    pub fn with_profiles(mut self, profiles: Vec<&str>) -> Self {
+        self.profiles
+            .push(profiles.iter().map(|str| str.to_string()).collect());
+        self
+    }
+
+    /// Resets build profile restrictions.
+    pub fn no_profiles(mut self) -> Self {
+        self.profiles = Vec::new();
+        self
+    }
+
+    /// Returns the dependency's package name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the dependency's architecture qualifier.
+    pub fn arch_qualifier(&self) -> Option<&str> {
+        self.arch_qualifier.as_deref()
+    }
+
+    /// Returns the dependency's version constraint.
+    pub fn version(&self) -> Option<&(DebVersionOperator, DebVersion)> {
+        self.version.as_ref()
+    }
+
+    /// Returns whether `version` satisfies the dependency's version constraint.
+    ///
+    /// This is always true if the dependency doesn't have a version constraint.
+    /// The package name and other restrictions aren't taken into account.
+    pub fn satisfied_by(&self, version: &DebVersion) -> bool {
+        match &self.version {
+            Some((operator, constraint)) => operator.compare(version, constraint),
+            None => true,
+        }
+    }
+
+    /// Returns the dependency's architecture restrictions.
+    pub fn architectures(&self) -> &Vec<String> {
+        &self.architectures
+    }
+
+    /// Returns whether the dependency applies when building for `architecture`,
+    /// according to its architecture restrictions.
+    ///
+    /// Restrictions may be wildcards such as `linux-any`. This is always true if
+    /// the dependency doesn't have any architecture restrictions.
+    pub fn applies_to(&self, architecture: &DebArchitecture) -> bool {
+        let matches = |name: &str| {
+            DebArchitectureWildcard::from(name).is_ok_and(|wildcard| wildcard.matches(architecture))
+        };
+        match self.architectures.first() {
+            None => true,
+            Some(first) if first.starts_with('!') => self
+                .architectures
+                .iter()
+                .all(|name| !matches(name.trim_start_matches('!'))),
+            Some(_) => self.architectures.iter().any(|name| matches(name)),
+        }
+    }
+
+    /// Returns the dependency's build profile restriction formulas.
+    pub fn profiles(&self) -> &Vec<Vec<String>> {
+        &self.profiles
+    }
+}
+
+impl fmt::Display for DebDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(qualifier) = &self.arch_qualifier {
+            write!(f, ":{}", qualifier)?;
+        }
+        if let Some((operator, version)) = &self.version {
+            write!(f, " ({} {})", operator.as_str(), version)?;
+        }
+        if !self.architectures.is_empty() {
+            write!(f, " [{}]", self.architectures.join(" "))?;
+        }
+        for profiles in &self.profiles {
+            write!(f, " <{}>", profiles.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry in a relationship field, such as Depends or Conflicts.
+///
+/// Each relation is made up of one or more alternative [DebDependency]s,
+/// separated by `|`, any of which satisfies the relation.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::*;
+///
+/// let relation = DebRelation::from("libc6 (>= 2.34) | libc6-compat [amd64] <!nocheck>").unwrap();
+/// assert_eq!(relation.alternatives()[1].name(), "libc6-compat");
+///
+/// let relation = DebRelation::new(DebDependency::new("libc6").set_version(
+///     DebVersionOperator::LaterOrEqual,
+///     DebVersion::from("2.34").unwrap(),
+/// ));
+/// assert_eq!(relation.to_string(), "libc6 (>= 2.34)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebRelation {
+    alternatives: Vec<DebDependency>,
+}
+
+impl DebRelation {
+    /// Creates a DebRelation satisfied only by `dependency`.
+    pub fn new(dependency: DebDependency) -> Self {
+        Self {
+            alternatives: vec![dependency],
+        }
+    }
+
+    /// Parses a DebRelation from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the alternatives in `input`
+    /// isn't a valid dependency.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        Ok(Self {
+            alternatives: input
+                .split('|')
+                .map(DebDependency::from)
+                .collect::<Result<Vec<DebDependency>, DebError>>()?,
+        })
+    }
+
+    /// Parses a comma-separated list of relations, as found in a relationship field.
+    ///
+    /// Empty entries, such as those left by a trailing comma, are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any relation in `input` is invalid.
+    pub fn from_list(input: &str) -> Result<Vec<Self>, DebError> {
+        input
+            .split(',')
+            .filter(|str| !str.trim().is_empty())
+            .map(Self::from)
+            .collect()
+    }
+
+    /// Adds an alternative dependency which also satisfies the relation.
+    pub fn or(mut self, dependency: DebDependency) -> Self {
+        self.alternatives.push(dependency);
+        self
+    }
+
+    /// Returns the relation's alternative dependencies.
+    pub fn alternatives(&self) -> &Vec<DebDependency> {
+        &self.alternatives
+    }
+}
+
+impl fmt::Display for DebRelation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternatives: Vec<String> = self.alternatives.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", alternatives.join(" | "))
+    }
+}
+
+// Package names are lowercase, at least two characters long, and start with a
+// letter or digit, as dpkg requires
+fn is_package_name(name: &str) -> bool {
+    name.len() >= 2 && is_valid_name(name)
+}
+
+// Package, architecture and profile names share a character set
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c))
+}
@@ -0,0 +1,1162 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Generates and reads the indices of APT repositories.
+//!
+//! This module can be used in place of `dpkg-scanpackages` to publish packages
+//! to an APT repository, and to look up packages in existing repositories. See
+//! the [Debian Repository Format][1] for how repositories are laid out.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # fn main() -> Result<(), deb_rust::DebError> {
+//! use deb_rust::{DebArchitecture, DebCompression, DebSigningKey};
+//! use deb_rust::repository::{DebPackagesIndex, DebRelease};
+//!
+//! let index = DebPackagesIndex::from_pool("repo", "pool/main")?;
+//! index.write_to_dir(
+//!     "repo/dists/stable/main/binary-amd64",
+//!     &[DebCompression::None, DebCompression::Gzip, DebCompression::Xz],
+//! )?;
+//!
+//! DebRelease::new("stable")
+//!     .set_origin("Example")
+//!     .with_architecture(DebArchitecture::Amd64)
+//!     .with_component("main")
+//!     .set_acquire_by_hash(true)
+//!     .with_dir("repo/dists/stable")?
+//!     .write_signed_to_dir(
+//!         "repo/dists/stable",
+//!         &DebSigningKey::from_path("signing-key.asc")?,
+//!     )?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [1]: https://wiki.debian.org/DebianRepository/Format
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::TimeZone;
+
+use crate::binary::{field_line, DebArchive, DebPackage, DebReader};
+use crate::compression::{decompress, Encoder};
+use crate::deb822::{fold_value, DebParagraph};
+use crate::error::DebError;
+use crate::openpgp::DebSigningKey;
+use crate::relation::DebRelation;
+use crate::shared::{
+    DebArchitecture, DebArchitectureWildcard, DebCompression, DebCompressionOptions, DebDigests,
+    DebLimits, DigestWriter,
+};
+use crate::version::DebVersion;
+
+/// A repository's `Packages` index, listing the binary packages available for
+/// one architecture of one component.
+///
+/// Each package gets a stanza containing its control fields, followed by its
+/// `Filename`, `Size`, `MD5sum`, `SHA1`, `SHA256` and `Description-md5` fields.
+///
+/// Existing indices can be read with `read()`, and their packages looked up
+/// with `find()`, `get()` and `latest()`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebPackagesIndex {
+    stanzas: Vec<DebParagraph>,
+    packages: Vec<DebIndexPackage>, // The stanzas, parsed
+}
+
+impl DebPackagesIndex {
+    /// Creates an empty DebPackagesIndex.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a Packages index from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't valid deb822 syntax,
+    /// or if any of its stanzas isn't a valid package. Line numbers in errors
+    /// are relative to the start of `input`.
+    pub fn parse(input: &str) -> Result<Self, DebError> {
+        let mut output = Self::new();
+        for (start, text) in split_paragraphs(input) {
+            let stanza = DebParagraph::parse(text).map_err(|e| offset_error(e, start))?;
+            if stanza.is_empty() {
+                continue;
+            }
+            let package = DebIndexPackage::parse(text).map_err(|e| offset_error(e, start))?;
+            output.stanzas.push(stanza);
+            output.packages.push(package);
+        }
+        Ok(output)
+    }
+
+    /// Reads a Packages index from `input`, which may be compressed with any
+    /// compression DebCompression supports, such as a `Packages.xz` file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` can't be decompressed, or
+    /// if the index is invalid.
+    pub fn read<R: Read>(input: R) -> Result<Self, DebError> {
+        Self::parse(&read_index(input)?)
+    }
+
+    /// Creates a DebPackagesIndex from every .deb file in `pool`, a directory
+    /// relative to the repository's `root`.
+    ///
+    /// Packages are added in the order of their paths, and their filenames are
+    /// relative to `root`. Only the control archive of each package is read.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `pool` can't be read, or if any of
+    /// the .deb files in it are invalid.
+    pub fn from_pool<P, Q>(root: P, pool: Q) -> Result<Self, DebError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let root = root.as_ref();
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for entry in walkdir::WalkDir::new(root.join(pool)) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.file_type().is_file()
+                && entry.path().extension().is_some_and(|ext| ext == "deb")
+            {
+                paths.push(entry.into_path());
+            }
+        }
+        paths.sort();
+
+        let mut output = Self::new();
+        for path in paths {
+            let reader = DebReader::new(File::open(&path)?)?;
+            let digests = DebDigests::from_reader(File::open(&path)?)?;
+            let filename = repository_path(path.strip_prefix(root).unwrap_or(&path));
+            output.push(stanza(reader.package(), &filename, &digests))?;
+        }
+        Ok(output)
+    }
+
+    /// Adds `archive` to the index, as it would be found at `filename`.
+    ///
+    /// `filename` is relative to the repository's root, such as
+    /// `pool/main/e/example/example_1.0_amd64.deb`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the archive's control file can't be read.
+    pub fn with_archive(mut self, archive: &DebArchive, filename: &str) -> Result<Self, DebError> {
+        let package = archive.control_package()?;
+        self.push(stanza(&package, filename, &archive.digests()?))?;
+        Ok(self)
+    }
+
+    /// Builds `package` and adds it to the index, as it would be found at `filename`.
+    ///
+    /// The package must be written to `filename` exactly as it's built here, so
+    /// its checksums match. Packages built reproducibly are best suited for this.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the package fails to build.
+    pub fn with_package(self, package: &DebPackage, filename: &str) -> Result<Self, DebError> {
+        self.with_archive(&package.build()?, filename)
+    }
+
+    /// Removes every package from the index.
+    pub fn no_packages(mut self) -> Self {
+        self.stanzas = Vec::new();
+        self.packages = Vec::new();
+        self
+    }
+
+    /// Returns the index's stanzas, one per package.
+    pub fn stanzas(&self) -> &Vec<DebParagraph> {
+        &self.stanzas
+    }
+
+    /// Returns the index's packages, in the same order as their stanzas.
+    pub fn packages(&self) -> &Vec<DebIndexPackage> {
+        &self.packages
+    }
+
+    /// Returns every version of package `name`, for every architecture.
+    pub fn find(&self, name: &str) -> Vec<&DebIndexPackage> {
+        self.packages
+            .iter()
+            .filter(|package| package.package.name() == name)
+            .collect()
+    }
+
+    /// Returns package `name` with `version`, built for `architecture`.
+    pub fn get(
+        &self,
+        name: &str,
+        version: &DebVersion,
+        architecture: &DebArchitecture,
+    ) -> Option<&DebIndexPackage> {
+        self.packages.iter().find(|package| {
+            package.package.name() == name
+                && package.package.version() == Some(version)
+                && package.package.architecture() == architecture
+        })
+    }
+
+    /// Returns the latest version of each package for each architecture, in
+    /// the order the packages first appear in the index.
+    pub fn latest(&self) -> Vec<&DebIndexPackage> {
+        latest(&self.packages, |package| {
+            (
+                format!(
+                    "{} {}",
+                    package.package.name(),
+                    package.package.architecture().as_str()
+                ),
+                package.package.version(),
+            )
+        })
+    }
+
+    /// Converts the index to deb822 syntax, with stanzas separated by blank lines.
+    pub fn serialize(&self) -> String {
+        self.stanzas
+            .iter()
+            .map(|stanza| stanza.serialize())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Adds a stanza, along with the package parsed from it
+    fn push(&mut self, stanza: DebParagraph) -> Result<(), DebError> {
+        self.packages
+            .push(DebIndexPackage::parse(&stanza.serialize())?);
+        self.stanzas.push(stanza);
+        Ok(())
+    }
+
+    /// Writes the index to `output`, compressed with `compression`.
+    pub fn write<W: Write>(&self, compression: DebCompression, output: W) -> Result<(), DebError> {
+        let mut encoder = Encoder::new(compression, &DebCompressionOptions::new(), output)?;
+        encoder.write_all(self.serialize().as_bytes())?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Writes the index to `dir` once for each of `compressions`, as `Packages`,
+    /// `Packages.gz`, `Packages.xz` and so on.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `dir` doesn't exist or can't be written to.
+    pub fn write_to_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        compressions: &[DebCompression],
+    ) -> Result<(), DebError> {
+        for compression in compressions {
+            let path = dir
+                .as_ref()
+                .join(format!("Packages{}", compression.extension()));
+            self.write(*compression, File::create(path)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// A package listed in a repository's Packages index.
+///
+/// The stanza's control fields are parsed the same way as a .deb file's control
+/// file, into a DebPackage without any files. Fields describing the .deb file
+/// itself are available through this struct's getters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebIndexPackage {
+    package: DebPackage, // The package's control fields
+    filename: String,    // Relative to the repository's root
+    size: u64,
+    md5: String,
+    sha1: String,
+    sha256: String,
+}
+
+impl DebIndexPackage {
+    /// Parses a single stanza of a Packages index.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a valid control file,
+    /// or if it's missing its Package, Filename or Size fields.
+    pub fn parse(input: &str) -> Result<Self, DebError> {
+        let package = DebPackage::from_control(input)?;
+        let required = |field: &str| match package.field(field) {
+            Some(value) => Ok(value.to_string()),
+            None => Err(missing_field(field)),
+        };
+        if package.name().is_empty() {
+            return Err(missing_field("Package"));
+        }
+        let filename = required("Filename")?;
+        let size = match required("Size")?.parse::<u64>() {
+            Ok(size) => size,
+            Err(_) => {
+                return Err(DebError::InvalidControlField {
+                    field: "Size".to_string(),
+                    line: field_line(input, "Size"),
+                    reason: "expected a number".to_string(),
+                })
+            }
+        };
+        let optional = |field: &str| package.field(field).unwrap_or_default().to_string();
+        Ok(Self {
+            filename,
+            size,
+            md5: optional("MD5sum"),
+            sha1: optional("SHA1"),
+            sha256: optional("SHA256"),
+            package,
+        })
+    }
+
+    /// Returns the package's control fields.
+    pub fn package(&self) -> &DebPackage {
+        &self.package
+    }
+
+    /// Returns the path of the .deb file, relative to the repository's root.
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the size of the .deb file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the .deb file's MD5 checksum, or an empty string if it isn't listed.
+    pub fn md5(&self) -> &str {
+        &self.md5
+    }
+
+    /// Returns the .deb file's SHA-1 checksum, or an empty string if it isn't listed.
+    pub fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    /// Returns the .deb file's SHA-256 checksum, or an empty string if it isn't listed.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+/// A repository's `Sources` index, listing the source packages available for
+/// one component.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebSourcesIndex {
+    sources: Vec<DebIndexSource>,
+}
+
+impl DebSourcesIndex {
+    /// Parses a Sources index from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't valid deb822 syntax,
+    /// or if any of its stanzas isn't a valid source package. Line numbers in
+    /// errors are relative to the start of `input`.
+    pub fn parse(input: &str) -> Result<Self, DebError> {
+        let mut sources: Vec<DebIndexSource> = Vec::new();
+        for (start, text) in split_paragraphs(input) {
+            let stanza = DebParagraph::parse(text).map_err(|e| offset_error(e, start))?;
+            if !stanza.is_empty() {
+                sources.push(
+                    DebIndexSource::from_paragraph(stanza, text)
+                        .map_err(|e| offset_error(e, start))?,
+                );
+            }
+        }
+        Ok(Self { sources })
+    }
+
+    /// Reads a Sources index from `input`, which may be compressed with any
+    /// compression DebCompression supports, such as a `Sources.xz` file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` can't be decompressed, or
+    /// if the index is invalid.
+    pub fn read<R: Read>(input: R) -> Result<Self, DebError> {
+        Self::parse(&read_index(input)?)
+    }
+
+    /// Returns the index's source packages.
+    pub fn sources(&self) -> &Vec<DebIndexSource> {
+        &self.sources
+    }
+
+    /// Returns every version of source package `name`.
+    pub fn find(&self, name: &str) -> Vec<&DebIndexSource> {
+        self.sources
+            .iter()
+            .filter(|source| source.name == name)
+            .collect()
+    }
+
+    /// Returns source package `name` with `version`.
+    pub fn get(&self, name: &str, version: &DebVersion) -> Option<&DebIndexSource> {
+        self.sources
+            .iter()
+            .find(|source| source.name == name && &source.version == version)
+    }
+
+    /// Returns the latest version of each source package, in the order the
+    /// packages first appear in the index.
+    pub fn latest(&self) -> Vec<&DebIndexSource> {
+        latest(&self.sources, |source| {
+            (source.name.clone(), Some(&source.version))
+        })
+    }
+}
+
+/// A source package listed in a repository's Sources index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebIndexSource {
+    name: String,
+    version: DebVersion,
+    binaries: Vec<String>,
+    architectures: Vec<DebArchitectureWildcard>,
+    build_depends: Vec<DebRelation>,
+    build_depends_indep: Vec<DebRelation>,
+    directory: String, // Relative to the repository's root
+    files: Vec<DebSourceFile>,
+    fields: DebParagraph, // Every field of the stanza, including those above
+}
+
+impl DebIndexSource {
+    /// Parses a single stanza of a Sources index.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't valid deb822 syntax,
+    /// if it's missing its Package or Version fields, or if any of its fields
+    /// has an invalid value.
+    pub fn parse(input: &str) -> Result<Self, DebError> {
+        Self::from_paragraph(DebParagraph::parse(input)?, input)
+    }
+
+    // Converts a parsed stanza, whose text is `input`, to DebIndexSource
+    fn from_paragraph(fields: DebParagraph, input: &str) -> Result<Self, DebError> {
+        // Values are joined back onto a single line, as in control files
+        let get = |field: &str| fields.get(field).map(|value| value.replace('\n', " "));
+        let in_field =
+            |field: &'static str| move |e: DebError| e.in_field(field, field_line(input, field));
+        let relations = |field: &'static str| match get(field) {
+            Some(value) => DebRelation::from_list(&value).map_err(in_field(field)),
+            None => Ok(Vec::new()),
+        };
+
+        let name = get("Package").ok_or_else(|| missing_field("Package"))?;
+        let version = match get("Version") {
+            Some(version) => DebVersion::from(&version).map_err(in_field("Version"))?,
+            None => return Err(missing_field("Version")),
+        };
+        let binaries = get("Binary")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|binary| binary.trim().to_string())
+                    .filter(|binary| !binary.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let architectures = get("Architecture")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(DebArchitectureWildcard::from)
+            .collect::<Result<Vec<DebArchitectureWildcard>, DebError>>()
+            .map_err(in_field("Architecture"))?;
+
+        // Files lists the MD5 checksums, and Checksums-Sha256 the SHA-256
+        // checksums, of the same files
+        let mut files: Vec<DebSourceFile> = Vec::new();
+        for (field, sha256) in [("Files", false), ("Checksums-Sha256", true)] {
+            for line in fields.get(field).unwrap_or_default().lines() {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                let (checksum, size, name) = match words[..] {
+                    [] => continue,
+                    [checksum, size, name] => match size.parse::<u64>() {
+                        Ok(size) => (checksum, size, name),
+                        Err(_) => return Err(in_field(field)(invalid_file(line))),
+                    },
+                    _ => return Err(in_field(field)(invalid_file(line))),
+                };
+                let index = match files.iter().position(|file| file.name == name) {
+                    Some(index) => index,
+                    None => {
+                        files.push(DebSourceFile {
+                            name: name.to_string(),
+                            size,
+                            md5: String::new(),
+                            sha256: String::new(),
+                        });
+                        files.len() - 1
+                    }
+                };
+                match sha256 {
+                    true => files[index].sha256 = checksum.to_string(),
+                    false => files[index].md5 = checksum.to_string(),
+                }
+            }
+        }
+
+        Ok(Self {
+            name,
+            version,
+            binaries,
+            architectures,
+            build_depends: relations("Build-Depends")?,
+            build_depends_indep: relations("Build-Depends-Indep")?,
+            directory: get("Directory").unwrap_or_default(),
+            files,
+            fields,
+        })
+    }
+
+    /// Returns the source package's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the source package's version.
+    pub fn version(&self) -> &DebVersion {
+        &self.version
+    }
+
+    /// Returns the names of the binary packages built from the source package.
+    pub fn binaries(&self) -> &Vec<String> {
+        &self.binaries
+    }
+
+    /// Returns the architectures the source package can be built for.
+    pub fn architectures(&self) -> &Vec<DebArchitectureWildcard> {
+        &self.architectures
+    }
+
+    /// Returns the source package's build dependencies.
+    pub fn build_depends(&self) -> &Vec<DebRelation> {
+        &self.build_depends
+    }
+
+    /// Returns the build dependencies only needed for architecture independent packages.
+    pub fn build_depends_indep(&self) -> &Vec<DebRelation> {
+        &self.build_depends_indep
+    }
+
+    /// Returns the directory containing the source package's files, relative
+    /// to the repository's root.
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// Returns the source package's files, such as its .dsc file and tarballs.
+    pub fn files(&self) -> &Vec<DebSourceFile> {
+        &self.files
+    }
+
+    /// Returns the value of field `name`, if it exists.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name)
+    }
+
+    /// Returns every field of the stanza.
+    pub fn fields(&self) -> &Vec<(String, String)> {
+        self.fields.fields()
+    }
+}
+
+/// A file belonging to a source package, such as its .dsc file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebSourceFile {
+    name: String, // Relative to the source package's directory
+    size: u64,
+    md5: String,
+    sha256: String,
+}
+
+impl DebSourceFile {
+    /// Returns the file's name, relative to the source package's directory.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the file's size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the file's MD5 checksum, or an empty string if it isn't listed.
+    pub fn md5(&self) -> &str {
+        &self.md5
+    }
+
+    /// Returns the file's SHA-256 checksum, or an empty string if it isn't listed.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+// Creates an error for a required field missing from a stanza
+fn missing_field(field: &str) -> DebError {
+    DebError::InvalidControlField {
+        field: field.to_string(),
+        line: 1,
+        reason: "field is missing".to_string(),
+    }
+}
+
+// Creates an error for a line of a source package's file list, whose field and
+// line are filled in by `in_field()`
+fn invalid_file(line: &str) -> DebError {
+    DebError::InvalidControlField {
+        field: String::new(),
+        line: 0,
+        reason: format!("invalid file entry {}", line.trim()),
+    }
+}
+
+// Returns the item with the latest version for each key, where `key` returns
+// an item's key and version
+fn latest<'a, T, F>(items: &'a [T], key: F) -> Vec<&'a T>
+where
+    F: Fn(&'a T) -> (String, Option<&'a DebVersion>),
+{
+    let mut output: Vec<&T> = Vec::new();
+    let mut positions: HashMap<String, (usize, Option<&DebVersion>)> = HashMap::new();
+    for item in items {
+        let (name, version) = key(item);
+        match positions.get_mut(&name) {
+            Some((index, latest)) if version > *latest => {
+                output[*index] = item;
+                *latest = version;
+            }
+            Some(_) => {}
+            None => {
+                positions.insert(name, (output.len(), version));
+                output.push(item);
+            }
+        }
+    }
+    output
+}
+
+// Reads a whole index, decompressing it if needed
+fn read_index<R: Read>(mut input: R) -> Result<String, DebError> {
+    let mut buf: Vec<u8> = Vec::new();
+    input.read_to_end(&mut buf)?;
+    let compression = DebCompression::from_magic(&buf).unwrap_or(DebCompression::None);
+    let mut output = String::new();
+    decompress(compression, buf.as_slice(), &DebLimits::new())?.read_to_string(&mut output)?;
+    Ok(output)
+}
+
+// Splits `input` into the text of each paragraph, along with the line each
+// paragraph starts on
+fn split_paragraphs(input: &str) -> Vec<(usize, &str)> {
+    let mut output = Vec::new();
+    let mut start: Option<(usize, usize)> = None; // The current paragraph's line and byte
+    let mut position = 0;
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        match (line.trim().is_empty(), start) {
+            (true, Some((line_number, byte))) => {
+                output.push((line_number, &input[byte..position]));
+                start = None;
+            }
+            (false, None) => start = Some((index + 1, position)),
+            _ => {}
+        }
+        position += line.len();
+    }
+    if let Some((line_number, byte)) = start {
+        output.push((line_number, &input[byte..]));
+    }
+    output
+}
+
+// Converts the line of an error within a paragraph starting on line `start`
+// to its line within the whole index
+fn offset_error(error: DebError, start: usize) -> DebError {
+    match error {
+        DebError::InvalidSyntax { line, reason } => DebError::InvalidSyntax {
+            line: line + start - 1,
+            reason,
+        },
+        DebError::InvalidControlField {
+            field,
+            line,
+            reason,
+        } => DebError::InvalidControlField {
+            field,
+            line: line + start - 1,
+            reason,
+        },
+        error => error,
+    }
+}
+
+// Creates the Packages stanza of `package`, found at `filename`
+fn stanza(package: &DebPackage, filename: &str, digests: &DebDigests) -> DebParagraph {
+    let mut output = package.control_paragraph();
+    output.set("Filename", filename);
+    output.set("Size", &digests.size().to_string());
+    output.set("MD5sum", digests.md5());
+    output.set("SHA1", digests.sha1());
+    output.set("SHA256", digests.sha256());
+    // APT identifies translations by the checksum of the untranslated description
+    if let Some(description) = output.get("Description") {
+        let checksum = format!("{:x}", md5::compute(format!("{}\n", description)));
+        output.set("Description-md5", &checksum);
+    }
+    output
+}
+
+// Converts a relative path to the `/` separated form used in repositories
+fn repository_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// A suite's `Release` file, describing the suite and listing the checksums of
+/// its indices.
+///
+/// Paths in the Release file are relative to the suite's directory, such as
+/// `dists/stable`. If the suite uses [Acquire-By-Hash][1], `write_to_dir()`
+/// also copies each file to `by-hash/SHA256/<checksum>` next to it, so clients
+/// never see a mix of old and new indices while the repository is updated.
+///
+/// Like [`DebParagraph::set()`], text fields are stored the way they'd be read
+/// back from the file, so a multi-line description is folded onto indented
+/// continuation lines.
+///
+/// [1]: https://wiki.debian.org/DebianRepository/Format#indices_acquisition_via_hashsums_.28by-hash.29
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebRelease {
+    origin: String,
+    label: String,
+    suite: String,
+    version: String,
+    codename: String,
+    date: Option<u64>, // Seconds since the Unix epoch, or the current time if unset
+    valid_until: Option<u64>, // Seconds since the Unix epoch
+    architectures: Vec<DebArchitecture>,
+    components: Vec<String>,
+    description: String,
+    acquire_by_hash: bool,
+    files: Vec<(String, DebDigests)>, // Paths relative to the suite's directory
+}
+
+impl DebRelease {
+    /// Creates a DebRelease for `suite`, such as `stable`.
+    pub fn new(suite: &str) -> Self {
+        Self {
+            origin: String::new(),
+            label: String::new(),
+            suite: fold_value(suite),
+            version: String::new(),
+            codename: String::new(),
+            date: None,
+            valid_until: None,
+            architectures: Vec::new(),
+            components: Vec::new(),
+            description: String::new(),
+            acquire_by_hash: false,
+            files: Vec::new(),
+        }
+    }
+
+    /// Sets the repository's origin.
+    pub fn set_origin(mut self, origin: &str) -> Self {
+        self.origin = fold_value(origin);
+        self
+    }
+
+    /// Sets the repository's label.
+    pub fn set_label(mut self, label: &str) -> Self {
+        self.label = fold_value(label);
+        self
+    }
+
+    /// Sets the suite's name.
+    pub fn set_suite(mut self, suite: &str) -> Self {
+        self.suite = fold_value(suite);
+        self
+    }
+
+    /// Sets the suite's version, such as `12.4`.
+    pub fn set_version(mut self, version: &str) -> Self {
+        self.version = fold_value(version);
+        self
+    }
+
+    /// Sets the suite's codename, such as `bookworm`.
+    pub fn set_codename(mut self, codename: &str) -> Self {
+        self.codename = fold_value(codename);
+        self
+    }
+
+    /// Sets the time the suite was published, in seconds since the Unix epoch.
+    ///
+    /// When unset, the current time is used.
+    pub fn set_date(mut self, date: u64) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Resets the publishing time, so the current time is used.
+    pub fn no_date(mut self) -> Self {
+        self.date = None;
+        self
+    }
+
+    /// Sets the time after which clients consider the suite out of date, in
+    /// seconds since the Unix epoch.
+    pub fn set_valid_until(mut self, valid_until: u64) -> Self {
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    /// Removes the suite's expiry time.
+    pub fn no_valid_until(mut self) -> Self {
+        self.valid_until = None;
+        self
+    }
+
+    /// Adds an architecture to the suite.
+    pub fn with_architecture(mut self, architecture: DebArchitecture) -> Self {
+        self.architectures.push(architecture);
+        self
+    }
+
+    /// Removes all of the suite's architectures.
+    pub fn no_architectures(mut self) -> Self {
+        self.architectures = Vec::new();
+        self
+    }
+
+    /// Adds a component, such as `main`, to the suite.
+    pub fn with_component(mut self, component: &str) -> Self {
+        self.components.push(fold_value(component));
+        self
+    }
+
+    /// Removes all of the suite's components.
+    pub fn no_components(mut self) -> Self {
+        self.components = Vec::new();
+        self
+    }
+
+    /// Sets the suite's description.
+    pub fn set_description(mut self, description: &str) -> Self {
+        self.description = fold_value(description);
+        self
+    }
+
+    /// Sets whether clients should download indices by their checksums.
+    pub fn set_acquire_by_hash(mut self, acquire_by_hash: bool) -> Self {
+        self.acquire_by_hash = acquire_by_hash;
+        self
+    }
+
+    /// Adds a file to the Release file's checksum lists, at `path` relative to
+    /// the suite's directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `path` is absolute or contains
+    /// `..`, as it could then point outside the suite's directory.
+    pub fn with_file(mut self, path: &str, digests: DebDigests) -> Result<Self, DebError> {
+        if Path::new(path)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(DebError::UnsafePath(PathBuf::from(path)));
+        }
+        self.files.retain(|(file, _)| file != path);
+        self.files.push((path.to_string(), digests));
+        Ok(self)
+    }
+
+    /// Adds every file in `dir`, the suite's directory, to the checksum lists.
+    ///
+    /// The Release file itself, its signatures and `by-hash` directories are skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `dir` can't be read.
+    pub fn with_dir<P: AsRef<Path>>(mut self, dir: P) -> Result<Self, DebError> {
+        let dir = dir.as_ref();
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for entry in walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != "by-hash")
+        {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.file_type().is_file() {
+                paths.push(entry.into_path());
+            }
+        }
+        paths.sort();
+
+        for path in paths {
+            let relative = repository_path(path.strip_prefix(dir).unwrap_or(&path));
+            if ["Release", "InRelease", "Release.gpg"].contains(&relative.as_str()) {
+                continue;
+            }
+            let digests = DebDigests::from_reader(File::open(&path)?)?;
+            self = self.with_file(&relative, digests)?;
+        }
+        Ok(self)
+    }
+
+    /// Removes every file from the checksum lists.
+    pub fn no_files(mut self) -> Self {
+        self.files = Vec::new();
+        self
+    }
+
+    /// Returns the repository's origin.
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// Returns the repository's label.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the suite's name.
+    pub fn suite(&self) -> &str {
+        &self.suite
+    }
+
+    /// Returns the suite's version.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the suite's codename.
+    pub fn codename(&self) -> &str {
+        &self.codename
+    }
+
+    /// Returns the time the suite was published, if it has been set.
+    pub fn date(&self) -> Option<u64> {
+        self.date
+    }
+
+    /// Returns the suite's expiry time, if it has been set.
+    pub fn valid_until(&self) -> Option<u64> {
+        self.valid_until
+    }
+
+    /// Returns the suite's architectures.
+    pub fn architectures(&self) -> &Vec<DebArchitecture> {
+        &self.architectures
+    }
+
+    /// Returns the suite's components.
+    pub fn components(&self) -> &Vec<String> {
+        &self.components
+    }
+
+    /// Returns the suite's description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns whether clients should download indices by their checksums.
+    pub fn acquire_by_hash(&self) -> bool {
+        self.acquire_by_hash
+    }
+
+    /// Returns the files in the checksum lists, with paths relative to the
+    /// suite's directory.
+    pub fn files(&self) -> &Vec<(String, DebDigests)> {
+        &self.files
+    }
+
+    /// Converts the Release file to deb822 syntax.
+    pub fn serialize(&self) -> String {
+        let date = release_date(
+            self.date
+                .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+        );
+        let valid_until = self.valid_until.map(release_date).unwrap_or_default();
+        let architectures = self
+            .architectures
+            .iter()
+            .map(|architecture| architecture.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let components = self.components.join(" ");
+        // Checksums are followed by the size, right aligned, and the path
+        let checksums = |checksum: fn(&DebDigests) -> &str| {
+            self.files
+                .iter()
+                .map(|(path, digests)| {
+                    format!("\n {} {:>16} {}", checksum(digests), digests.size(), path)
+                })
+                .collect::<String>()
+        };
+        let md5sums = checksums(DebDigests::md5);
+        let sha256sums = checksums(DebDigests::sha256);
+
+        let fields = [
+            ["Origin", self.origin.as_str()],
+            ["Label", self.label.as_str()],
+            ["Suite", self.suite.as_str()],
+            ["Version", self.version.as_str()],
+            ["Codename", self.codename.as_str()],
+            ["Date", date.as_str()],
+            ["Valid-Until", valid_until.as_str()],
+            ["Architectures", architectures.as_str()],
+            ["Components", components.as_str()],
+            ["Description", self.description.as_str()],
+            [
+                "Acquire-By-Hash",
+                match self.acquire_by_hash {
+                    true => "yes",
+                    false => "",
+                },
+            ],
+            ["MD5Sum", md5sums.as_str()],
+            ["SHA256", sha256sums.as_str()],
+        ];
+        let mut paragraph = DebParagraph::new();
+        for field in fields {
+            if !field[1].is_empty() {
+                paragraph.set(field[0], field[1]);
+            }
+        }
+        paragraph.serialize()
+    }
+
+    /// Writes the Release file to `output`.
+    pub fn write<W: Write>(&self, mut output: W) -> Result<(), DebError> {
+        output.write_all(self.serialize().as_bytes())?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Writes the Release file to `dir`, the suite's directory.
+    ///
+    /// If the suite uses Acquire-By-Hash, each listed file is also copied to
+    /// `by-hash/SHA256/<checksum>` in the directory containing it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `dir` can't be written to, or if
+    /// the suite uses Acquire-By-Hash and a listed file doesn't exist in `dir`
+    /// or no longer matches its digests.
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), DebError> {
+        let dir = dir.as_ref();
+        self.write_by_hash(dir)?;
+        self.write(File::create(dir.join("Release"))?)
+    }
+
+    /// Writes the Release file to `dir`, the suite's directory, along with its
+    /// signatures made with `key`: `InRelease`, a cleartext signed copy of the
+    /// Release file, and `Release.gpg`, a detached signature of it.
+    ///
+    /// APT verifies these against the keys in `/etc/apt/trusted.gpg.d` or the
+    /// source's `Signed-By` option, which `key.armored_public_key()` can be used
+    /// to create.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error under the same conditions as
+    /// `write_to_dir()`.
+    pub fn write_signed_to_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        key: &DebSigningKey,
+    ) -> Result<(), DebError> {
+        let dir = dir.as_ref();
+        self.write_by_hash(dir)?;
+        // The Release file is only serialized once, as its date may change
+        let release = self.serialize();
+        fs::write(dir.join("Release"), &release)?;
+        fs::write(dir.join("Release.gpg"), key.sign(release.as_bytes()))?;
+        fs::write(dir.join("InRelease"), key.clearsign(&release))?;
+        Ok(())
+    }
+
+    // Copies each listed file to `by-hash/SHA256/<checksum>` next to it, if the
+    // suite uses Acquire-By-Hash. Files are checked against their digests as
+    // they're copied, and only renamed into place if they match, so a file that
+    // changed since it was added is never published under the wrong checksum.
+    fn write_by_hash(&self, dir: &Path) -> Result<(), DebError> {
+        if self.acquire_by_hash {
+            for (path, digests) in &self.files {
+                let path = dir.join(path);
+                let by_hash = match path.parent() {
+                    Some(parent) => parent.join("by-hash").join("SHA256"),
+                    None => continue,
+                };
+                fs::create_dir_all(&by_hash)?;
+                let temporary = by_hash.join(format!(".{}.tmp", digests.sha256()));
+                let mut input = File::open(&path)?;
+                let mut output = File::create(&temporary)?;
+                let mut digest = DigestWriter::new();
+                let mut buf = vec![0; 64 * 1024];
+                loop {
+                    let read = input.read(&mut buf)?;
+                    if read == 0 {
+                        break;
+                    }
+                    output.write_all(&buf[..read])?;
+                    digest.write_all(&buf[..read])?;
+                }
+                drop(output);
+                if digest.finish() != *digests {
+                    fs::remove_file(&temporary)?;
+                    return Err(DebError::FileChanged(path));
+                }
+                fs::rename(&temporary, by_hash.join(digests.sha256()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Formats a time in seconds since the Unix epoch the way Release files do
+fn release_date(time: u64) -> String {
+    match chrono::Utc.timestamp_opt(time as i64, 0).single() {
+        Some(time) => time.format("%a, %d %b %Y %H:%M:%S UTC").to_string(),
+        None => String::new(),
+    }
+}
@@ -16,18 +16,27 @@
     along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::fmt;
 use std::fs;
-use std::io::{Error, ErrorKind};
+use std::io::Read;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::DebError;
 
 /// Represents the [various architectures Deb supports](https://wiki.debian.org/SupportedArchitectures).
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Architectures without a variant of their own, such as `musl-linux-amd64`, are
+/// represented by `Custom`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DebArchitecture {
     /// For architecture independent packages, such as interpreted software
     /// or configuration files.
     All,
+    /// For source packages which can be built on any architecture.
+    Any,
     Alpha,
     /// Arm versions 5T and 6
     Armel,
@@ -41,26 +50,38 @@ pub enum DebArchitecture {
     /// 64-bit x86_64
     Amd64,
     Ia64,
+    /// 64-bit LoongArch
+    Loong64,
     M68k,
     Mips,
     /// Little-endian 32-bit
     Mipsel,
+    /// Big-endian 64-bit
+    Mips64,
     /// Little-endian 64-bit
     Mips64el,
+    /// Little-endian 32-bit MIPS release 6
+    Mipsr6el,
+    /// Little-endian 64-bit MIPS release 6
+    Mips64r6el,
     PowerPC,
     Ppc64,
     Ppc64el,
     Riscv64,
     S390x,
     Sh4,
-    Sparc4,
+    Sparc64,
     X32,
     /// 32-bit x86 for GNU/Hurd
     HurdI386,
+    /// 64-bit x86_64 for GNU/Hurd
+    HurdAmd64,
     /// 32-bit x86 for FreeBSD
     KFreebsdI386,
     /// 64-bit x86_64 for FreeBSD
     KFreebsdAmd64,
+    /// Any other architecture, by its dpkg name.
+    Custom(String),
 }
 
 impl DebArchitecture {
@@ -68,69 +89,232 @@ impl DebArchitecture {
     pub fn as_str(&self) -> &str {
         match self {
             DebArchitecture::All => "all",
-            DebArchitecture::Alpha => "Alpha",
-            DebArchitecture::Armel => "Armel",
+            DebArchitecture::Any => "any",
+            DebArchitecture::Alpha => "alpha",
+            DebArchitecture::Armel => "armel",
             DebArchitecture::Armhf => "armhf",
             DebArchitecture::Arm64 => "arm64",
             DebArchitecture::Hppa => "hppa",
             DebArchitecture::I386 => "i386",
             DebArchitecture::Amd64 => "amd64",
             DebArchitecture::Ia64 => "ia64",
+            DebArchitecture::Loong64 => "loong64",
             DebArchitecture::M68k => "m68k",
             DebArchitecture::Mips => "mips",
             DebArchitecture::Mipsel => "mipsel",
+            DebArchitecture::Mips64 => "mips64",
             DebArchitecture::Mips64el => "mips64el",
-            DebArchitecture::PowerPC => "PowerPC",
-            DebArchitecture::Ppc64 => "PPC64",
+            DebArchitecture::Mipsr6el => "mipsr6el",
+            DebArchitecture::Mips64r6el => "mips64r6el",
+            DebArchitecture::PowerPC => "powerpc",
+            DebArchitecture::Ppc64 => "ppc64",
             DebArchitecture::Ppc64el => "ppc64el",
             DebArchitecture::Riscv64 => "riscv64",
             DebArchitecture::S390x => "s390x",
-            DebArchitecture::Sh4 => "SH4",
-            DebArchitecture::Sparc4 => "sparc4",
+            DebArchitecture::Sh4 => "sh4",
+            DebArchitecture::Sparc64 => "sparc64",
             DebArchitecture::X32 => "x32",
             DebArchitecture::HurdI386 => "hurd-i386",
+            DebArchitecture::HurdAmd64 => "hurd-amd64",
             DebArchitecture::KFreebsdI386 => "kfreebsd-i386",
             DebArchitecture::KFreebsdAmd64 => "kfreebsd-amd64",
+            DebArchitecture::Custom(name) => name,
         }
     }
 
     /// Converts &str to DebArchitecture.
     ///
-    /// This function will return an error if the given string doesn't match
-    /// any architecture name.
-    pub fn from(input: &str) -> std::io::Result<Self> {
+    /// Names without a variant of their own are converted to `Custom`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given string isn't a valid
+    /// architecture name. As with dpkg, names may only contain ASCII letters,
+    /// digits and `-`, and must start with a letter or digit. Wildcards such
+    /// as `linux-any` aren't architectures, so any name other than `any` with
+    /// an `any` component is rejected; use [`DebArchitectureWildcard`] for
+    /// those.
+    pub fn from(input: &str) -> Result<Self, DebError> {
         match input {
             "all" => Ok(DebArchitecture::All),
-            "Alpha" => Ok(DebArchitecture::Alpha),
-            "Armel" => Ok(DebArchitecture::Armel),
+            "any" => Ok(DebArchitecture::Any),
+            "alpha" => Ok(DebArchitecture::Alpha),
+            "armel" => Ok(DebArchitecture::Armel),
             "armhf" => Ok(DebArchitecture::Armhf),
             "arm64" => Ok(DebArchitecture::Arm64),
             "hppa" => Ok(DebArchitecture::Hppa),
             "i386" => Ok(DebArchitecture::I386),
             "amd64" => Ok(DebArchitecture::Amd64),
             "ia64" => Ok(DebArchitecture::Ia64),
+            "loong64" => Ok(DebArchitecture::Loong64),
             "m68k" => Ok(DebArchitecture::M68k),
             "mips" => Ok(DebArchitecture::Mips),
             "mipsel" => Ok(DebArchitecture::Mipsel),
+            "mips64" => Ok(DebArchitecture::Mips64),
             "mips64el" => Ok(DebArchitecture::Mips64el),
-            "PowerPC" => Ok(DebArchitecture::PowerPC),
-            "PPC64" => Ok(DebArchitecture::Ppc64),
+            "mipsr6el" => Ok(DebArchitecture::Mipsr6el),
+            "mips64r6el" => Ok(DebArchitecture::Mips64r6el),
+            "powerpc" => Ok(DebArchitecture::PowerPC),
+            "ppc64" => Ok(DebArchitecture::Ppc64),
             "ppc64el" => Ok(DebArchitecture::Ppc64el),
             "riscv64" => Ok(DebArchitecture::Riscv64),
             "s390x" => Ok(DebArchitecture::S390x),
-            "SH4" => Ok(DebArchitecture::Sh4),
-            "sparc4" => Ok(DebArchitecture::Sparc4),
+            "sh4" => Ok(DebArchitecture::Sh4),
+            "sparc64" => Ok(DebArchitecture::Sparc64),
             "x32" => Ok(DebArchitecture::X32),
             "hurd-i386" => Ok(DebArchitecture::HurdI386),
+            "hurd-amd64" => Ok(DebArchitecture::HurdAmd64),
             "kfreebsd-i386" => Ok(DebArchitecture::KFreebsdI386),
             "kfreebsd-amd64" => Ok(DebArchitecture::KFreebsdAmd64),
-            &_ => Err(Error::new(ErrorKind::Other, "invalid architecture name")),
+            _ if is_arch_name(input) && !input.split('-').any(|part| part == "any") => {
+                Ok(DebArchitecture::Custom(input.to_string()))
+            }
+            _ => Err(DebError::UnknownArchitecture(input.to_string())),
+        }
+    }
+
+    /// Returns the architecture's dpkg tuple, in the form `[abi, libc, os, cpu]`.
+    ///
+    /// For example, `armhf` is `["eabihf", "gnu", "linux", "arm"]` and
+    /// `musl-linux-amd64` is `["base", "musl", "linux", "amd64"]`. `all` and
+    /// `any` aren't real architectures, so they have no tuple.
+    pub fn tuple(&self) -> Option<[String; 4]> {
+        if matches!(self, DebArchitecture::All | DebArchitecture::Any) {
+            return None;
+        }
+
+        // The name is the CPU, optionally prefixed with the libc and OS, or
+        // just the OS when the libc is the OS's usual one
+        let name = self.as_str();
+        let (libc, os, cpu) = match name.rsplit_once('-') {
+            None => ("gnu", "linux", name),
+            Some((system, cpu)) => match system.split_once('-') {
+                Some((libc, os)) => (libc, os, cpu),
+                None => match system {
+                    "darwin" | "freebsd" | "netbsd" | "openbsd" => ("bsd", system, cpu),
+                    "solaris" => ("sysv", system, cpu),
+                    _ => ("gnu", system, cpu),
+                },
+            },
+        };
+        if os.contains('-')
+            || [libc, os, cpu]
+                .iter()
+                .any(|part| part.is_empty() || *part == "any")
+        {
+            return None;
+        }
+
+        // Some names describe the ABI as well as the CPU
+        let (abi, cpu) = match cpu {
+            "armel" => ("eabi", "arm"),
+            "armeb" => ("eabi", "armeb"),
+            "armhf" => ("eabihf", "arm"),
+            "arm64ilp32" => ("ilp32", "arm64"),
+            "x32" => ("x32", "amd64"),
+            "powerpcspe" => ("spe", "powerpc"),
+            "mips64" | "mips64el" | "mips64r6" | "mips64r6el" => ("abi64", cpu),
+            "mipsn32" => ("abin32", "mips64"),
+            "mipsn32el" => ("abin32", "mips64el"),
+            "mipsn32r6" => ("abin32", "mips64r6"),
+            "mipsn32r6el" => ("abin32", "mips64r6el"),
+            _ => ("base", cpu),
+        };
+        Some([abi, libc, os, cpu].map(|part| part.to_string()))
+    }
+}
+
+// Returns whether `input` is a valid architecture name or wildcard, as dpkg
+// defines them
+fn is_arch_name(input: &str) -> bool {
+    input.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// An architecture wildcard, such as `linux-any` or `any-amd64`, used to restrict
+/// packages and dependencies to a set of architectures.
+///
+/// Wildcards are matched against an architecture's [dpkg tuple][1]. Each part of
+/// the wildcard that isn't `any` must equal the matching part of the tuple.
+/// Wildcards without `any` in them match only their own architecture.
+///
+/// [1]: https://manpages.debian.org/unstable/dpkg-dev/dpkg-architecture.1.en.html#TERMS
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::{DebArchitecture, DebArchitectureWildcard};
+///
+/// let wildcard = DebArchitectureWildcard::from("linux-any").unwrap();
+/// assert!(wildcard.matches(&DebArchitecture::Amd64));
+/// assert!(!wildcard.matches(&DebArchitecture::HurdI386));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebArchitectureWildcard {
+    name: String,       // The wildcard as it was written
+    tuple: Vec<String>, // The wildcard's tuple, or empty for `all` and `any`
+}
+
+impl DebArchitectureWildcard {
+    /// Converts &str to DebArchitectureWildcard.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given string isn't a valid
+    /// architecture name or wildcard.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        if !is_arch_name(input) {
+            return Err(DebError::UnknownArchitecture(input.to_string()));
+        }
+        let mut parts: Vec<String> = input.splitn(4, '-').map(str::to_string).collect();
+        let tuple = match parts.iter().any(|part| part == "any") {
+            // Missing parts at the start of the wildcard match anything
+            true => {
+                while parts.len() < 4 {
+                    parts.insert(0, "any".to_string());
+                }
+                parts
+            }
+            false => match DebArchitecture::from(input)?.tuple() {
+                Some(tuple) => tuple.to_vec(),
+                None => Vec::new(),
+            },
+        };
+        Ok(Self {
+            name: input.to_string(),
+            tuple,
+        })
+    }
+
+    /// Returns the wildcard as a string.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether `architecture` matches the wildcard.
+    pub fn matches(&self, architecture: &DebArchitecture) -> bool {
+        if self.name == "any" || self.name == architecture.as_str() {
+            return true;
+        }
+        match architecture.tuple() {
+            Some(real) if !self.tuple.is_empty() => self
+                .tuple
+                .iter()
+                .zip(real.iter())
+                .all(|(wildcard, real)| wildcard == "any" || wildcard == real),
+            _ => false,
         }
     }
 }
 
+impl fmt::Display for DebArchitectureWildcard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
 /// Used for [Deb's Priority field](https://www.debian.org/doc/debian-policy/ch-archive.html#s-priorities).
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DebPriority {
     Required,
     Important,
@@ -155,45 +339,477 @@ impl DebPriority {
     ///
     /// This function will return in error if the given string doesn't match
     /// any priority name.
-    pub fn from(input: &str) -> std::io::Result<Self> {
+    pub fn from(input: &str) -> Result<Self, DebError> {
         match input {
             "required" => Ok(DebPriority::Required),
             "important" => Ok(DebPriority::Important),
             "standard" => Ok(DebPriority::Standard),
             "optional" => Ok(DebPriority::Optional),
             "extra" => Ok(DebPriority::Extra),
-            &_ => Err(Error::new(ErrorKind::Other, "invalid priority name")),
+            &_ => Err(DebError::UnknownPriority(input.to_string())),
+        }
+    }
+}
+
+/// Used for [Deb's Multi-Arch field](https://wiki.debian.org/Multiarch/HOWTO).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebMultiArch {
+    /// The package can be co-installed with itself for other architectures.
+    Same,
+    /// The package can satisfy dependencies of packages of other architectures.
+    Foreign,
+    /// Reverse dependencies may choose to depend on the package with `:any`.
+    Allowed,
+    No,
+}
+
+impl DebMultiArch {
+    /// Converts DebMultiArch to &str.
+    pub fn as_str(&self) -> &str {
+        match self {
+            DebMultiArch::Same => "same",
+            DebMultiArch::Foreign => "foreign",
+            DebMultiArch::Allowed => "allowed",
+            DebMultiArch::No => "no",
+        }
+    }
+
+    /// Converts &str to DebMultiArch.
+    ///
+    /// This function will return an error if the given string doesn't match
+    /// any Multi-Arch value.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        match input {
+            "same" => Ok(DebMultiArch::Same),
+            "foreign" => Ok(DebMultiArch::Foreign),
+            "allowed" => Ok(DebMultiArch::Allowed),
+            "no" => Ok(DebMultiArch::No),
+            &_ => Err(DebError::UnknownMultiArch(input.to_string())),
         }
     }
 }
 
 /// Used to configure which compression format is used for data and control archives.
 ///
-/// Zstd is preferred, though XZ is available as a legacy option.
-#[derive(Debug, PartialEq, Eq)]
+/// Zstd is preferred, though XZ is available as a legacy option. The remaining formats
+/// are mostly found in older or third-party packages, and are supported for compatibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DebCompression {
     Xz,
     Zstd,
+    Gzip,
+    Bzip2,
+    /// The legacy `.lzma` format, superseded by XZ.
+    Lzma,
+    /// An uncompressed tar archive.
+    None,
+}
+
+impl DebCompression {
+    /// Returns the file extension used for archives with this compression,
+    /// including the leading dot.
+    ///
+    /// This is an empty string for uncompressed archives.
+    pub fn extension(&self) -> &str {
+        match self {
+            DebCompression::Xz => ".xz",
+            DebCompression::Zstd => ".zst",
+            DebCompression::Gzip => ".gz",
+            DebCompression::Bzip2 => ".bz2",
+            DebCompression::Lzma => ".lzma",
+            DebCompression::None => "",
+        }
+    }
+
+    /// Returns whether dpkg accepts the compression for a package's control
+    /// archive. Unlike the data archive, it can't be compressed with bzip2 or lzma.
+    pub fn supports_control(&self) -> bool {
+        !matches!(self, DebCompression::Bzip2 | DebCompression::Lzma)
+    }
+
+    /// Detects the compression of an archive from its name, such as `data.tar.xz`.
+    ///
+    /// Returns `None` if the name doesn't end in `.tar` or a known compression extension.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.split_once(".tar")?;
+        match extension {
+            ".xz" => Some(DebCompression::Xz),
+            ".zst" => Some(DebCompression::Zstd),
+            ".gz" => Some(DebCompression::Gzip),
+            ".bz2" => Some(DebCompression::Bzip2),
+            ".lzma" => Some(DebCompression::Lzma),
+            "" => Some(DebCompression::None),
+            &_ => None,
+        }
+    }
+
+    /// Detects the compression of an archive from its first bytes.
+    ///
+    /// Returns `None` if the bytes don't match the magic number of any supported
+    /// format. Uncompressed archives are only detected if `bytes` is long enough
+    /// to contain the tar header's magic number.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(DebCompression::Xz)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(DebCompression::Zstd)
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(DebCompression::Gzip)
+        } else if bytes.starts_with(b"BZh") {
+            Some(DebCompression::Bzip2)
+        } else if bytes.starts_with(&[0x5d, 0x00, 0x00]) {
+            Some(DebCompression::Lzma)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(DebCompression::None)
+        } else {
+            None
+        }
+    }
+}
+
+/// Used to tune how a package's archives are compressed.
+///
+/// Options which don't apply to the chosen [DebCompression] are ignored. For example,
+/// only XZ and Zstd can compress with multiple threads.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::*;
+/// use deb_rust::binary::DebPackage;
+///
+/// let mut package = DebPackage::new("example")
+///     .set_compression(DebCompression::Zstd)
+///     .set_compression_options(
+///         DebCompressionOptions::new()
+///             .set_level(19)
+///             .set_threads(8)
+///             .set_long_distance_matching(true),
+///     );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DebCompressionOptions {
+    level: Option<i32>,      // Uses each format's default level when unset
+    threads: u32,            // 0 and 1 both compress on the current thread
+    extreme: bool,           // XZ and LZMA's extreme presets
+    long_distance: bool,     // Zstd's long distance matching
+    window_log: Option<u32>, // Zstd's maximum back-reference distance, as a power of 2
+}
+
+impl DebCompressionOptions {
+    /// Creates DebCompressionOptions using each format's default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the compression level.
+    ///
+    /// Valid levels are 0-9 for XZ, LZMA and Gzip, 1-9 for Bzip2, and -7-22 for Zstd.
+    /// When unset, XZ, LZMA, Gzip and Bzip2 use level 9, and Zstd uses its default level.
+    pub fn set_level(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Resets the compression level to the format's default.
+    pub fn no_level(mut self) -> Self {
+        self.level = None;
+        self
+    }
+
+    /// Sets the number of threads used by XZ and Zstd.
+    pub fn set_threads(mut self, threads: u32) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets whether XZ and LZMA use their slower "extreme" presets.
+    pub fn set_extreme(mut self, extreme: bool) -> Self {
+        self.extreme = extreme;
+        self
+    }
+
+    /// Sets whether Zstd uses long distance matching, which helps with large archives.
+    pub fn set_long_distance_matching(mut self, long_distance: bool) -> Self {
+        self.long_distance = long_distance;
+        self
+    }
+
+    /// Sets Zstd's window log, the maximum back-reference distance as a power of 2.
+    ///
+    /// Windows larger than 2^27 bytes require more memory when decompressing, and
+    /// dpkg may refuse to install packages using them.
+    pub fn set_window_log(mut self, window_log: u32) -> Self {
+        self.window_log = Some(window_log);
+        self
+    }
+
+    /// Resets Zstd's window log to its default.
+    pub fn no_window_log(mut self) -> Self {
+        self.window_log = None;
+        self
+    }
+
+    /// Returns the compression level, if it has been set.
+    pub fn level(&self) -> Option<i32> {
+        self.level
+    }
+
+    /// Returns the number of threads used by XZ and Zstd.
+    pub fn threads(&self) -> u32 {
+        self.threads
+    }
+
+    /// Returns whether XZ and LZMA use their extreme presets.
+    pub fn extreme(&self) -> bool {
+        self.extreme
+    }
+
+    /// Returns whether Zstd uses long distance matching.
+    pub fn long_distance_matching(&self) -> bool {
+        self.long_distance
+    }
+
+    /// Returns Zstd's window log, if it has been set.
+    pub fn window_log(&self) -> Option<u32> {
+        self.window_log
+    }
+}
+
+/// Limits on the resources used while reading a package.
+///
+/// Packages from untrusted sources can claim huge sizes or decompress to far
+/// more data than they contain. Reading such a package with limits set returns
+/// a `DebError::LimitExceeded` error instead of exhausting memory. By default,
+/// only the memory used by decompressors is limited, to windows of 128 MiB, and
+/// no other limits are set.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> Result<(), deb_rust::DebError> {
+/// use std::fs::File;
+/// use deb_rust::DebLimits;
+/// use deb_rust::binary::DebArchive;
+///
+/// let archive = DebArchive::read_with_limits(
+///     File::open("example.deb")?,
+///     DebLimits::new()
+///         .set_max_member_size(512 * 1024 * 1024)
+///         .set_max_decompressed_size(2 * 1024 * 1024 * 1024)
+///         .set_max_entries(100_000),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebLimits {
+    max_member_size: Option<u64>,       // Largest ar member read into memory
+    max_decompressed_size: Option<u64>, // Per archive, after decompression
+    max_entries: Option<usize>,         // Entries per archive
+    max_window_log: u32,                // Largest decompressor window, as a power of 2
+}
+
+impl Default for DebLimits {
+    fn default() -> Self {
+        Self {
+            max_member_size: None,
+            max_decompressed_size: None,
+            max_entries: None,
+            max_window_log: 27,
+        }
+    }
+}
+
+impl DebLimits {
+    /// Creates DebLimits with the default decompressor window, and no other limits set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest size, in bytes, of an ar member read into memory.
+    ///
+    /// DebReader doesn't read the data archive into memory, so this only limits
+    /// its control archive.
+    pub fn set_max_member_size(mut self, size: u64) -> Self {
+        self.max_member_size = Some(size);
+        self
+    }
+
+    /// Removes the limit on the size of ar members.
+    pub fn no_max_member_size(mut self) -> Self {
+        self.max_member_size = None;
+        self
+    }
+
+    /// Sets the largest size, in bytes, the control and data archives may each
+    /// decompress to.
+    pub fn set_max_decompressed_size(mut self, size: u64) -> Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
+
+    /// Removes the limit on the decompressed size of the archives.
+    pub fn no_max_decompressed_size(mut self) -> Self {
+        self.max_decompressed_size = None;
+        self
+    }
+
+    /// Sets the largest number of entries the control and data archives may each have.
+    pub fn set_max_entries(mut self, entries: usize) -> Self {
+        self.max_entries = Some(entries);
+        self
+    }
+
+    /// Removes the limit on the number of entries in the archives.
+    pub fn no_max_entries(mut self) -> Self {
+        self.max_entries = None;
+        self
+    }
+
+    /// Sets the largest window, as a power of 2, decompressors may use. Defaults
+    /// to 27, a window of 128 MiB.
+    ///
+    /// Zstd archives compressed with a larger window, such as with
+    /// `DebCompressionOptions::set_window_log()`, need this raised to be read.
+    /// XZ and LZMA decoders are limited to dictionaries of this size, so every
+    /// preset can be read with the default. Zstd supports windows of up to 2^31
+    /// bytes on 64-bit targets, and 2^30 bytes on 32-bit targets.
+    pub fn set_max_window_log(mut self, window_log: u32) -> Self {
+        self.max_window_log = window_log;
+        self
+    }
+
+    /// Returns the largest size of an ar member read into memory, if it has been set.
+    pub fn max_member_size(&self) -> Option<u64> {
+        self.max_member_size
+    }
+
+    /// Returns the largest decompressed size of each archive, if it has been set.
+    pub fn max_decompressed_size(&self) -> Option<u64> {
+        self.max_decompressed_size
+    }
+
+    /// Returns the largest number of entries in each archive, if it has been set.
+    pub fn max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    /// Returns the largest window, as a power of 2, decompressors may use.
+    pub fn max_window_log(&self) -> u32 {
+        self.max_window_log
+    }
+}
+
+// Opens a new reader over a lazily read file's contents
+type DebFileOpener = Arc<dyn Fn() -> std::io::Result<Box<dyn Read>> + Send + Sync>;
+
+// Where a DebFile's contents come from. Files on disk and readers are only
+// read while the package is being built, so their contents never need to be
+// held in memory all at once.
+#[derive(Clone)]
+enum DebFileContents {
+    Buf(Vec<u8>),
+    Path(PathBuf),
+    Reader(DebFileOpener),
+}
+
+impl fmt::Debug for DebFileContents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebFileContents::Buf(buf) => f.debug_tuple("Buf").field(buf).finish(),
+            DebFileContents::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            DebFileContents::Reader(_) => f.write_str("Reader"),
+        }
+    }
+}
+
+// Readers can't be compared by their contents, so they're only equal if they
+// are the same reader
+impl PartialEq for DebFileContents {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DebFileContents::Buf(a), DebFileContents::Buf(b)) => a == b,
+            (DebFileContents::Path(a), DebFileContents::Path(b)) => a == b,
+            (DebFileContents::Reader(a), DebFileContents::Reader(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DebFileContents {}
+
+/// The kind of entry a DebFile represents in a package's data archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebFileKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Dir,
+    /// A symbolic link pointing to the contained path, which may be relative.
+    Symlink(PathBuf),
+    /// A hard link to the file at the contained path in the package, which must
+    /// come before the link in the package's files.
+    Hardlink(PathBuf),
 }
 
 /// Used in the DebPackage struct to represent files in a package's archives.
 ///
 /// This struct contains the file's contents, permissions, and it's path in
-/// the final package.
-#[derive(Debug)]
+/// the final package. Besides regular files, a DebFile may also represent a
+/// directory, symlink or hardlink, as described by its [DebFileKind].
+///
+/// A file's contents may either be held in memory, or read lazily from a path
+/// or reader when the package is built, which keeps memory usage low when
+/// building packages with large files.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DebFile {
-    contents: Vec<u8>, // The contents of the file
-    mode: u32,         // The file's permissions in octal form
-    path: PathBuf,     // The path the file goes to in the archive
-    mtime: u64,        // The modification time
+    contents: DebFileContents, // The contents of the file
+    kind: DebFileKind,         // Whether the file is a regular file, directory or link
+    mode: u32,                 // The file's permissions in octal form
+    path: PathBuf,             // The path the file goes to in the archive
+    mtime: u64,                // The modification time
+    uid: u64,                  // The ID of the user owning the file
+    gid: u64,                  // The ID of the group owning the file
+    uname: String,             // The name of the user owning the file
+    gname: String,             // The name of the group owning the file
+    conffile: bool,            // Whether the file is a configuration file
 }
 
 impl DebFile {
+    // Creates a DebFile at `to`, owned by root:root and modified now, which the
+    // public constructors build on
+    fn new<T: AsRef<std::ffi::OsStr>>(
+        contents: DebFileContents,
+        kind: DebFileKind,
+        mode: u32,
+        to: T,
+    ) -> Self {
+        Self {
+            contents,
+            kind,
+            mode,
+            path: PathBuf::from(&to),
+            mtime: chrono::Utc::now().timestamp() as u64,
+            uid: 0,
+            gid: 0,
+            uname: String::from("root"),
+            gname: String::from("root"),
+            conffile: false,
+        }
+    }
+
     /// Creates a DebFile from a path.
     ///
     /// `from` is a path to a file on your system that you're trying to add to the package.
     /// `to` is where the file will go once the package is installed on a user's system.
     ///
+    /// The file's contents aren't read until the package is built, so `from`
+    /// must still exist at that point.
+    ///
+    /// Like `dpkg-deb --root-owner-group`, the file is owned by `root:root`
+    /// regardless of who owns `from`.
+    ///
     /// On Unix systems, the file's mode will automatically be set based on `from`.
     /// On Windows, the file's mode will be set to `33188`.
     ///
@@ -214,32 +830,51 @@ impl DebFile {
     ///     ).unwrap());
     /// ```
     #[cfg(unix)]
-    pub fn from_path<F, T>(from: F, to: T) -> std::io::Result<Self>
+    pub fn from_path<F, T>(from: F, to: T) -> Result<Self, DebError>
     where
         F: AsRef<Path>,
         T: AsRef<std::ffi::OsStr>,
     {
-        Ok(Self {
-            contents: fs::read(&from)?,
-            mode: fs::File::open(&from)?.metadata()?.mode(),
-            path: PathBuf::from(&to),
-            mtime: fs::File::open(&from)?.metadata()?.modified()?.duration_since(std::time::SystemTime::UNIX_EPOCH).unwrap().as_secs(),
-        })
+        // Symlinks are added as links rather than followed
+        let metadata = fs::symlink_metadata(&from)?;
+        let kind = if metadata.is_dir() {
+            DebFileKind::Dir
+        } else if metadata.file_type().is_symlink() {
+            DebFileKind::Symlink(fs::read_link(&from)?)
+        } else {
+            DebFileKind::File
+        };
+        let contents = match kind {
+            DebFileKind::File => DebFileContents::Path(from.as_ref().to_path_buf()),
+            _ => DebFileContents::Buf(Vec::new()),
+        };
+        let mut output = Self::new(contents, kind, metadata.mode(), to);
+        output.mtime = metadata
+            .modified()?
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Ok(output)
     }
 
     // Same function but for Windows, as file modes are a Unix feature
     #[cfg(windows)]
-    pub fn from_path<F, T>(from: F, to: T) -> std::io::Result<Self>
+    pub fn from_path<F, T>(from: F, to: T) -> Result<Self, DebError>
     where
         F: AsRef<Path>,
         T: AsRef<std::ffi::OsStr>,
     {
-        Ok(Self {
-            contents: fs::read(&from)?,
-            mode: 33188,
-            path: PathBuf::from(&to),
-            mtime: 13332,
-        })
+        if fs::metadata(&from)?.is_dir() {
+            return Ok(Self::new_dir(to));
+        }
+        let mut output = Self::new(
+            DebFileContents::Path(from.as_ref().to_path_buf()),
+            DebFileKind::File,
+            33188,
+            to,
+        );
+        output.mtime = 13332;
+        Ok(output)
     }
 
     /// Creates a DebFile from a buffer.
@@ -267,12 +902,105 @@ impl DebFile {
     where
         T: AsRef<std::ffi::OsStr>,
     {
-        Self {
-            contents: buf,
-            mode: 33188,
-            path: PathBuf::from(&to),
-            mtime: chrono::Utc::now().timestamp() as u64,
-        }
+        Self::new(DebFileContents::Buf(buf), DebFileKind::File, 33188, to)
+    }
+
+    /// Creates a DebFile whose contents are read from a reader.
+    ///
+    /// `open` is called to create a new reader each time the file's contents are
+    /// needed, which happens twice while the package is built: once to calculate
+    /// the file's size and checksum, and once to write it to the archive. Each
+    /// reader must therefore produce the same contents.
+    /// `to` is where the file will go once the package is installed on a user's system.
+    ///
+    /// The file's mode is set to 33188. Permission's must be managed manually.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use deb_rust::DebFile;
+    /// use deb_rust::binary::DebPackage;
+    ///
+    /// let mut package = DebPackage::new("example")
+    ///     .with_file(DebFile::from_reader(
+    ///         || File::open("target/release/example"),
+    ///         "/usr/bin/example",
+    ///     ).is_exec());
+    /// ```
+    pub fn from_reader<F, R, T>(open: F, to: T) -> Self
+    where
+        F: Fn() -> std::io::Result<R> + Send + Sync + 'static,
+        R: Read + 'static,
+        T: AsRef<std::ffi::OsStr>,
+    {
+        let contents =
+            DebFileContents::Reader(Arc::new(move || Ok(Box::new(open()?) as Box<dyn Read>)));
+        Self::new(contents, DebFileKind::File, 33188, to)
+    }
+
+    /// Creates a directory entry.
+    ///
+    /// `to` is where the directory will be created once the package is installed
+    /// on a user's system. Only the directory itself is created; its contents
+    /// must be added separately.
+    ///
+    /// The directory's mode is set to 16877 (`drwxr-xr-x`).
+    pub fn new_dir<T: AsRef<std::ffi::OsStr>>(to: T) -> Self {
+        Self::new(
+            DebFileContents::Buf(Vec::new()),
+            DebFileKind::Dir,
+            16877,
+            to,
+        )
+    }
+
+    /// Creates a symlink entry at `to`, pointing to `target`.
+    ///
+    /// `target` may be relative to the directory containing `to`, such as
+    /// `libfoo.so.1` for a link at `/usr/lib/libfoo.so`.
+    ///
+    /// The link's mode is set to 41471 (`lrwxrwxrwx`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use deb_rust::DebFile;
+    /// use deb_rust::binary::DebPackage;
+    ///
+    /// let mut package = DebPackage::new("example")
+    ///     .with_file(DebFile::new_symlink("libfoo.so.1", "/usr/lib/libfoo.so"));
+    /// ```
+    pub fn new_symlink<P, T>(target: P, to: T) -> Self
+    where
+        P: AsRef<Path>,
+        T: AsRef<std::ffi::OsStr>,
+    {
+        Self::new(
+            DebFileContents::Buf(Vec::new()),
+            DebFileKind::Symlink(target.as_ref().to_path_buf()),
+            41471,
+            to,
+        )
+    }
+
+    /// Creates a hardlink entry at `to`, linking to the file at `target`.
+    ///
+    /// `target` must be the path of a regular file in the same package, which
+    /// has to be added to the package before the hardlink.
+    ///
+    /// The link's mode is set to 33188. Permission's must be managed manually.
+    pub fn new_hardlink<P, T>(target: P, to: T) -> Self
+    where
+        P: AsRef<Path>,
+        T: AsRef<std::ffi::OsStr>,
+    {
+        Self::new(
+            DebFileContents::Buf(Vec::new()),
+            DebFileKind::Hardlink(target.as_ref().to_path_buf()),
+            33188,
+            to,
+        )
     }
 
     /// Sets the file's mode to have executable permissions.
@@ -287,9 +1015,22 @@ impl DebFile {
         self
     }
 
+    /// Sets whether the file is a [configuration file][1].
+    ///
+    /// dpkg preserves changes made to configuration files by the user when the
+    /// package is upgraded. This only applies to regular files.
+    ///
+    /// [1]: https://www.debian.org/doc/debian-policy/ch-files.html#configuration-files
+    pub fn set_conffile(mut self, conffile: bool) -> Self {
+        self.conffile = conffile;
+        self
+    }
+
     /// Sets the file's contents to `contents`.
+    ///
+    /// Contents are ignored for directories and links.
     pub fn set_contents(mut self, contents: Vec<u8>) -> Self {
-        self.contents = contents;
+        self.contents = DebFileContents::Buf(contents);
         self
     }
 
@@ -299,15 +1040,103 @@ impl DebFile {
         self
     }
 
+    /// Sets the file's modification time to `mtime`, in seconds since the Unix epoch.
+    pub fn set_mtime(mut self, mtime: u64) -> Self {
+        self.mtime = mtime;
+        self
+    }
+
+    /// Sets the user owning the file to `uname`, with ID `uid`.
+    ///
+    /// Files are owned by `root` by default. dpkg looks up the user by name
+    /// when installing the package, and only falls back to `uid` if no user
+    /// named `uname` exists.
+    pub fn set_owner(mut self, uid: u64, uname: &str) -> Self {
+        self.uid = uid;
+        self.uname = uname.to_string();
+        self
+    }
+
+    /// Sets the group owning the file to `gname`, with ID `gid`.
+    ///
+    /// Files are owned by the `root` group by default. Like the owner, the group
+    /// is looked up by name first.
+    pub fn set_group(mut self, gid: u64, gname: &str) -> Self {
+        self.gid = gid;
+        self.gname = gname.to_string();
+        self
+    }
+
     /// Sets the file's path to `to`.
     pub fn set_path<T: AsRef<std::ffi::OsStr>>(mut self, to: T) -> Self {
         self.path = PathBuf::from(&to);
         self
     }
 
-    /// Returns the file's contents.
-    pub fn contents(&self) -> &Vec<u8> {
-        &self.contents
+    /// Returns the ID of the user owning the file.
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+
+    /// Returns the ID of the group owning the file.
+    pub fn gid(&self) -> u64 {
+        self.gid
+    }
+
+    /// Returns the name of the user owning the file.
+    pub fn uname(&self) -> &str {
+        &self.uname
+    }
+
+    /// Returns the name of the group owning the file.
+    pub fn gname(&self) -> &str {
+        &self.gname
+    }
+
+    /// Returns whether the file is a configuration file.
+    pub fn conffile(&self) -> bool {
+        self.conffile
+    }
+
+    /// Returns what kind of entry the file is.
+    pub fn kind(&self) -> &DebFileKind {
+        &self.kind
+    }
+
+    /// Returns the file's contents if they are held in memory.
+    ///
+    /// This returns `None` for files created with `from_path()` or `from_reader()`.
+    /// Use `open()` or `read_contents()` to read the contents of any file.
+    pub fn contents(&self) -> Option<&Vec<u8>> {
+        match &self.contents {
+            DebFileContents::Buf(buf) => Some(buf),
+            _ => None,
+        }
+    }
+
+    /// Opens a reader over the file's contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file's contents are read from a
+    /// path or reader which can't be opened.
+    pub fn open(&self) -> Result<Box<dyn Read + '_>, DebError> {
+        Ok(match &self.contents {
+            DebFileContents::Buf(buf) => Box::new(buf.as_slice()),
+            DebFileContents::Path(path) => Box::new(fs::File::open(path)?),
+            DebFileContents::Reader(open) => open()?,
+        })
+    }
+
+    /// Reads the file's entire contents into memory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file's contents can't be read.
+    pub fn read_contents(&self) -> Result<Vec<u8>, DebError> {
+        let mut buf: Vec<u8> = Vec::new();
+        self.open()?.read_to_end(&mut buf)?;
+        Ok(buf)
     }
 
     /// Returns the file's mode.
@@ -325,3 +1154,114 @@ impl DebFile {
         self.mtime
     }
 }
+
+/// The size and checksums of a .deb file or one of its members, in the form
+/// used by repository indexes such as `Packages` files.
+///
+/// Checksums are formatted as lowercase hexadecimal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebDigests {
+    size: u64,      // The size in bytes
+    md5: String,    // The MD5 checksum
+    sha1: String,   // The SHA-1 checksum
+    sha256: String, // The SHA-256 checksum
+    sha512: String, // The SHA-512 checksum
+}
+
+impl DebDigests {
+    /// Calculates the digests of everything read from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` can't be read.
+    pub fn from_reader<R: Read>(mut input: R) -> Result<Self, DebError> {
+        let mut writer = DigestWriter::new();
+        std::io::copy(&mut input, &mut writer)?;
+        Ok(writer.finish())
+    }
+
+    /// Calculates the digests of `buf`.
+    pub fn from_buf(buf: &[u8]) -> Self {
+        let mut writer = DigestWriter::new();
+        writer.update(buf);
+        writer.finish()
+    }
+
+    /// Returns the size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the MD5 checksum.
+    pub fn md5(&self) -> &str {
+        &self.md5
+    }
+
+    /// Returns the SHA-1 checksum.
+    pub fn sha1(&self) -> &str {
+        &self.sha1
+    }
+
+    /// Returns the SHA-256 checksum.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /// Returns the SHA-512 checksum.
+    pub fn sha512(&self) -> &str {
+        &self.sha512
+    }
+}
+
+// Calculates DebDigests of everything written to it
+pub(crate) struct DigestWriter {
+    size: u64,
+    md5: md5::Context,
+    sha1: sha1::Sha1,
+    sha256: sha2::Sha256,
+    sha512: sha2::Sha512,
+}
+
+impl DigestWriter {
+    pub(crate) fn new() -> Self {
+        use sha2::Digest;
+        Self {
+            size: 0,
+            md5: md5::Context::new(),
+            sha1: sha1::Sha1::new(),
+            sha256: sha2::Sha256::new(),
+            sha512: sha2::Sha512::new(),
+        }
+    }
+
+    fn update(&mut self, buf: &[u8]) {
+        use sha2::Digest;
+        self.size += buf.len() as u64;
+        self.md5.consume(buf);
+        self.sha1.update(buf);
+        self.sha256.update(buf);
+        self.sha512.update(buf);
+    }
+
+    pub(crate) fn finish(self) -> DebDigests {
+        use sha2::Digest;
+        DebDigests {
+            size: self.size,
+            md5: format!("{:x}", self.md5.compute()),
+            sha1: format!("{:x}", self.sha1.finalize()),
+            sha256: format!("{:x}", self.sha256.finalize()),
+            sha512: format!("{:x}", self.sha512.finalize()),
+        }
+    }
+}
+
+impl std::io::Write for DigestWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+use crate::error::DebError;
+use crate::relation::DebRelation;
+
+/// A single entry of a package's `shlibs` control file, mapping a shared library
+/// to the dependencies needed to use it.
+///
+/// Each entry has the format `[type:] library version dependencies`, such as
+/// `libfoo 1 libfoo1 (>= 1.2)` for `libfoo.so.1`. Read [deb-shlibs(5)][1] for
+/// more details.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::{DebRelation, DebShlib};
+///
+/// let shlib = DebShlib::new("libfoo", "1")
+///     .with_dependency(DebRelation::from("libfoo1 (>= 1.2)").unwrap());
+/// assert_eq!(shlib.to_string(), "libfoo 1 libfoo1 (>= 1.2)");
+/// ```
+///
+/// [1]: https://manpages.debian.org/unstable/dpkg-dev/deb-shlibs.5.en.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebShlib {
+    package_type: Option<String>, // The package type the entry applies to, such as udeb
+    library: String,              // The library's name, without the .so suffix
+    version: String,              // The library's soname version
+    dependencies: Vec<DebRelation>, // The dependencies of packages linking to the library
+}
+
+impl DebShlib {
+    /// Creates a DebShlib for `library` with soname version `version`.
+    pub fn new(library: &str, version: &str) -> Self {
+        Self {
+            package_type: None,
+            library: library.to_string(),
+            version: version.to_string(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Parses a DebShlib from a single line of a shlibs file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the line is missing the library's
+    /// name or version, or if its dependencies are invalid.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        let mut rest = input.trim();
+        let mut package_type = None;
+        if let Some((first, after)) = rest.split_once(char::is_whitespace) {
+            if let Some(name) = first.strip_suffix(':') {
+                package_type = Some(name.to_string());
+                rest = after.trim_start();
+            }
+        }
+
+        let mut words = rest.splitn(3, char::is_whitespace);
+        let (library, version) = match (words.next(), words.next()) {
+            (Some(library), Some(version)) if !library.is_empty() && !version.is_empty() => {
+                (library, version)
+            }
+            _ => {
+                return Err(DebError::InvalidMember {
+                    member: "shlibs".to_string(),
+                    line: 1,
+                    reason: format!("invalid entry {}", input.trim()),
+                })
+            }
+        };
+        let dependencies = DebRelation::from_list(words.next().unwrap_or_default())?;
+
+        Ok(Self {
+            package_type,
+            library: library.to_string(),
+            version: version.to_string(),
+            dependencies,
+        })
+    }
+
+    /// Parses every entry in the contents of a shlibs file.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any entry in `input` is invalid.
+    pub fn from_list(input: &str) -> Result<Vec<Self>, DebError> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(index, line)| Self::from(line).map_err(|e| e.in_member("shlibs", index + 1)))
+            .collect()
+    }
+
+    /// Sets the package type the entry applies to, such as `udeb`.
+    pub fn set_package_type(mut self, package_type: &str) -> Self {
+        self.package_type = Some(package_type.to_string());
+        self
+    }
+
+    /// Resets the package type, so the entry applies to regular packages.
+    pub fn no_package_type(mut self) -> Self {
+        self.package_type = None;
+        self
+    }
+
+    /// Adds a dependency for packages linking to the library.
+    pub fn with_dependency(mut self, dependency: DebRelation) -> Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    /// Resets the entry's dependencies.
+    pub fn no_dependencies(mut self) -> Self {
+        self.dependencies = Vec::new();
+        self
+    }
+
+    /// Returns the package type the entry applies to, if any.
+    pub fn package_type(&self) -> Option<&str> {
+        self.package_type.as_deref()
+    }
+
+    /// Returns the library's name.
+    pub fn library(&self) -> &str {
+        &self.library
+    }
+
+    /// Returns the library's soname version.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the dependencies of packages linking to the library.
+    pub fn dependencies(&self) -> &Vec<DebRelation> {
+        &self.dependencies
+    }
+}
+
+impl fmt::Display for DebShlib {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(package_type) = &self.package_type {
+            write!(f, "{}: ", package_type)?;
+        }
+        write!(f, "{} {}", self.library, self.version)?;
+        let dependencies: Vec<String> = self.dependencies.iter().map(|d| d.to_string()).collect();
+        if !dependencies.is_empty() {
+            write!(f, " {}", dependencies.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+use crate::error::DebError;
+use crate::version::DebVersion;
+
+/// A symbol exported by a shared library, listed in a package's `symbols`
+/// control file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebSymbol {
+    name: String,            // The symbol's name, including its version and any tags
+    version: DebVersion,     // The earliest package version providing the symbol
+    dependency: Option<u32>, // The index of the dependency template to use
+}
+
+impl DebSymbol {
+    /// Creates a DebSymbol for `name`, which was first provided in `version`.
+    ///
+    /// `name` includes the symbol's version, such as `foo@Base` or `foo@LIBFOO_1.0`,
+    /// and any tags, such as `(c++)"foo::bar()@Base"`.
+    pub fn new(name: &str, version: DebVersion) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+            dependency: None,
+        }
+    }
+
+    /// Parses a DebSymbol from a single symbol line of a symbols file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the line is missing the symbol's
+    /// version, or if its version or dependency index are invalid.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        let input = input.trim();
+
+        // Names may be quoted when they contain spaces, such as C++ symbols
+        let mut in_quotes = false;
+        let name_end = input
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                !in_quotes && c.is_whitespace()
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(input.len());
+        let name = &input[..name_end];
+
+        let mut words = input[name_end..].split_whitespace();
+        let version = match words.next() {
+            Some(version) => DebVersion::from(version)?,
+            None => {
+                return Err(DebError::InvalidMember {
+                    member: "symbols".to_string(),
+                    line: 1,
+                    reason: format!("symbol {} is missing a version", name),
+                })
+            }
+        };
+        let dependency = match words.next() {
+            Some(id) => match id.parse::<u32>() {
+                Ok(id) => Some(id),
+                Err(_) => {
+                    return Err(DebError::InvalidMember {
+                        member: "symbols".to_string(),
+                        line: 1,
+                        reason: format!("invalid dependency index {}", id),
+                    })
+                }
+            },
+            None => None,
+        };
+        if words.next().is_some() {
+            return Err(DebError::InvalidMember {
+                member: "symbols".to_string(),
+                line: 1,
+                reason: format!("invalid symbol {}", input),
+            });
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            version,
+            dependency,
+        })
+    }
+
+    /// Sets which dependency template the symbol uses, where `0` is the main
+    /// dependency and `1` and up are the alternatives.
+    pub fn set_dependency(mut self, dependency: u32) -> Self {
+        self.dependency = Some(dependency);
+        self
+    }
+
+    /// Resets the symbol to use the main dependency template.
+    pub fn no_dependency(mut self) -> Self {
+        self.dependency = None;
+        self
+    }
+
+    /// Returns the symbol's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the earliest package version providing the symbol.
+    pub fn version(&self) -> &DebVersion {
+        &self.version
+    }
+
+    /// Returns the index of the dependency template the symbol uses, if set.
+    pub fn dependency(&self) -> Option<u32> {
+        self.dependency
+    }
+}
+
+impl fmt::Display for DebSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.version)?;
+        if let Some(dependency) = self.dependency {
+            write!(f, " {}", dependency)?;
+        }
+        Ok(())
+    }
+}
+
+/// The symbols of a single shared library, as listed in a package's `symbols`
+/// control file.
+///
+/// A symbols file may list several libraries. Read [deb-symbols(5)][1] for
+/// more details.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::{DebSymbol, DebSymbols, DebVersion};
+///
+/// let symbols = DebSymbols::new("libfoo.so.1", "libfoo1 #MINVER#")
+///     .with_field("Build-Depends-Package", "libfoo-dev")
+///     .with_symbol(DebSymbol::new("foo_init@Base", DebVersion::from("1.0").unwrap()));
+/// ```
+///
+/// [1]: https://manpages.debian.org/unstable/dpkg-dev/deb-symbols.5.en.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebSymbols {
+    soname: String,                // The library's soname, such as libfoo.so.1
+    dependency: String,            // The main dependency template
+    alternatives: Vec<String>,     // Alternative dependency templates
+    fields: Vec<(String, String)>, // Meta-information fields, such as Build-Depends-Package
+    symbols: Vec<DebSymbol>,       // The symbols the library exports
+}
+
+impl DebSymbols {
+    /// Creates an empty DebSymbols for the library `soname`.
+    ///
+    /// `dependency` is the template used to generate dependencies on the library,
+    /// such as `libfoo1 #MINVER#`, where `#MINVER#` is replaced with the minimum
+    /// version of the symbols used.
+    pub fn new(soname: &str, dependency: &str) -> Self {
+        Self {
+            soname: soname.to_string(),
+            dependency: dependency.to_string(),
+            alternatives: Vec::new(),
+            fields: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    /// Parses every library in the contents of a symbols file.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` doesn't start with a
+    /// library, or if any of its lines are invalid.
+    pub fn from_list(input: &str) -> Result<Vec<Self>, DebError> {
+        let mut output: Vec<Self> = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Lines that aren't indented start a new library, except for
+            // alternative dependencies and fields
+            if !line.starts_with([' ', '\t', '|', '*']) {
+                let (soname, dependency) = match line.trim().split_once(char::is_whitespace) {
+                    Some((soname, dependency)) => (soname, dependency.trim()),
+                    None => {
+                        return Err(DebError::InvalidMember {
+                            member: "symbols".to_string(),
+                            line: index + 1,
+                            reason: "library is missing a dependency".to_string(),
+                        });
+                    }
+                };
+                output.push(Self::new(soname, dependency));
+                continue;
+            }
+
+            let library = match output.last_mut() {
+                Some(library) => library,
+                None => {
+                    return Err(DebError::InvalidMember {
+                        member: "symbols".to_string(),
+                        line: index + 1,
+                        reason: "symbol without a library".to_string(),
+                    });
+                }
+            };
+            let line = line.trim();
+            if let Some(alternative) = line.strip_prefix('|') {
+                library.alternatives.push(alternative.trim().to_string());
+            } else if let Some(field) = line.strip_prefix('*') {
+                match field.split_once(':') {
+                    Some((name, value)) => library
+                        .fields
+                        .push((name.trim().to_string(), value.trim().to_string())),
+                    None => {
+                        return Err(DebError::InvalidMember {
+                            member: "symbols".to_string(),
+                            line: index + 1,
+                            reason: "missing field separator".to_string(),
+                        });
+                    }
+                }
+            } else {
+                library
+                    .symbols
+                    .push(DebSymbol::from(line).map_err(|e| e.in_member("symbols", index + 1))?);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Adds an alternative dependency template.
+    pub fn with_alternative(mut self, dependency: &str) -> Self {
+        self.alternatives.push(dependency.to_string());
+        self
+    }
+
+    /// Resets the library's alternative dependency templates.
+    pub fn no_alternatives(mut self) -> Self {
+        self.alternatives = Vec::new();
+        self
+    }
+
+    /// Adds the meta-information field `name` with `value`, such as
+    /// `Build-Depends-Package`.
+    pub fn with_field(mut self, name: &str, value: &str) -> Self {
+        self.fields.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Resets the library's meta-information fields.
+    pub fn no_fields(mut self) -> Self {
+        self.fields = Vec::new();
+        self
+    }
+
+    /// Adds a symbol to the library.
+    pub fn with_symbol(mut self, symbol: DebSymbol) -> Self {
+        self.symbols.push(symbol);
+        self
+    }
+
+    /// Resets the library's symbols.
+    pub fn no_symbols(mut self) -> Self {
+        self.symbols = Vec::new();
+        self
+    }
+
+    /// Returns the library's soname.
+    pub fn soname(&self) -> &str {
+        &self.soname
+    }
+
+    /// Returns the library's main dependency template.
+    pub fn dependency(&self) -> &str {
+        &self.dependency
+    }
+
+    /// Returns the library's alternative dependency templates.
+    pub fn alternatives(&self) -> &Vec<String> {
+        &self.alternatives
+    }
+
+    /// Returns the library's meta-information fields.
+    pub fn fields(&self) -> &Vec<(String, String)> {
+        &self.fields
+    }
+
+    /// Returns the library's symbols.
+    pub fn symbols(&self) -> &Vec<DebSymbol> {
+        &self.symbols
+    }
+}
+
+impl fmt::Display for DebSymbols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.soname, self.dependency)?;
+        for alternative in &self.alternatives {
+            writeln!(f, "| {}", alternative)?;
+        }
+        for (name, value) in &self.fields {
+            writeln!(f, "* {}: {}", name, value)?;
+        }
+        for symbol in &self.symbols {
+            writeln!(f, " {}", symbol)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+use crate::deb822::{fold_value, DebParagraph};
+use crate::error::DebError;
+
+/// The type of a debconf question, which decides how it's presented to the user.
+///
+/// Read [debconf-devel(7)][1] for what each type does.
+///
+/// [1]: https://manpages.debian.org/unstable/debconf-doc/debconf-devel.7.en.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebTemplateType {
+    Select,
+    Multiselect,
+    String,
+    Boolean,
+    Note,
+    Text,
+    Password,
+    Title,
+    Error,
+}
+
+impl DebTemplateType {
+    /// Returns the type as it appears in a templates file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DebTemplateType::Select => "select",
+            DebTemplateType::Multiselect => "multiselect",
+            DebTemplateType::String => "string",
+            DebTemplateType::Boolean => "boolean",
+            DebTemplateType::Note => "note",
+            DebTemplateType::Text => "text",
+            DebTemplateType::Password => "password",
+            DebTemplateType::Title => "title",
+            DebTemplateType::Error => "error",
+        }
+    }
+
+    /// Parses a DebTemplateType from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a known type.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        match input {
+            "select" => Ok(DebTemplateType::Select),
+            "multiselect" => Ok(DebTemplateType::Multiselect),
+            "string" => Ok(DebTemplateType::String),
+            "boolean" => Ok(DebTemplateType::Boolean),
+            "note" => Ok(DebTemplateType::Note),
+            "text" => Ok(DebTemplateType::Text),
+            "password" => Ok(DebTemplateType::Password),
+            "title" => Ok(DebTemplateType::Title),
+            "error" => Ok(DebTemplateType::Error),
+            &_ => Err(DebError::InvalidMember {
+                member: "templates".to_string(),
+                line: 1,
+                reason: format!("unknown template type {}", input),
+            }),
+        }
+    }
+}
+
+/// A debconf template, as found in a package's `templates` control file.
+///
+/// Fields other than `Template`, `Type`, `Default`, `Choices` and `Description`,
+/// such as translations like `Description-de.UTF-8`, are kept as additional fields.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::{DebTemplate, DebTemplateType};
+///
+/// let template = DebTemplate::new("example/enable", DebTemplateType::Boolean)
+///     .set_default("true")
+///     .set_description("Enable the example service?");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebTemplate {
+    name: String,                   // The question's name, such as package/question
+    template_type: DebTemplateType, // How the question is presented
+    default: Option<String>,        // The question's default answer
+    choices: Option<String>,        // The comma-separated choices of select questions
+    description: String,            // The short and extended description, folded
+    extra: DebParagraph,            // Fields not covered above
+}
+
+impl DebTemplate {
+    /// Creates a DebTemplate for the question `name`.
+    pub fn new(name: &str, template_type: DebTemplateType) -> Self {
+        Self {
+            name: name.to_string(),
+            template_type,
+            default: None,
+            choices: None,
+            description: String::new(),
+            extra: DebParagraph::new(),
+        }
+    }
+
+    /// Parses every template in the contents of a templates file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is not valid deb822 syntax,
+    /// or if any template is missing its `Template` or `Type` field.
+    pub fn from_list(input: &str) -> Result<Vec<Self>, DebError> {
+        let mut output = Vec::new();
+        let paragraphs = match DebParagraph::parse_all(input) {
+            Ok(paragraphs) => paragraphs,
+            Err(e) => return Err(e.in_member("templates", 1)),
+        };
+        for paragraph in paragraphs {
+            let name = match paragraph.get("Template") {
+                Some(name) => name,
+                None => {
+                    return Err(DebError::InvalidMember {
+                        member: "templates".to_string(),
+                        line: template_line(input, output.len()),
+                        reason: "template is missing Template field".to_string(),
+                    })
+                }
+            };
+            let template_type = match paragraph.get("Type") {
+                Some(template_type) => DebTemplateType::from(template_type)
+                    .map_err(|e| e.in_member("templates", template_line(input, output.len())))?,
+                None => {
+                    return Err(DebError::InvalidMember {
+                        member: "templates".to_string(),
+                        line: template_line(input, output.len()),
+                        reason: format!("template {} is missing Type field", name),
+                    })
+                }
+            };
+            let mut template = Self::new(name, template_type);
+            for (key, value) in paragraph.fields() {
+                match key.to_lowercase().as_str() {
+                    "template" | "type" => {}
+                    "default" => template.default = Some(value.clone()),
+                    "choices" => template.choices = Some(value.clone()),
+                    "description" => template.description = value.clone(),
+                    &_ => template.extra.set(key, value),
+                }
+            }
+            output.push(template);
+        }
+        Ok(output)
+    }
+
+    /// Sets the question's default answer.
+    pub fn set_default(mut self, default: &str) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    /// Resets the question's default answer.
+    pub fn no_default(mut self) -> Self {
+        self.default = None;
+        self
+    }
+
+    /// Sets the choices of a select or multiselect question, separated by commas.
+    pub fn set_choices(mut self, choices: &str) -> Self {
+        self.choices = Some(choices.to_string());
+        self
+    }
+
+    /// Resets the question's choices.
+    pub fn no_choices(mut self) -> Self {
+        self.choices = None;
+        self
+    }
+
+    /// Sets the question's description.
+    ///
+    /// The first line is the short description, and any following lines are the
+    /// extended description.
+    pub fn set_description(mut self, description: &str) -> Self {
+        self.description = fold_value(description);
+        self
+    }
+
+    /// Adds the additional field `name`, such as a translated description.
+    ///
+    /// If the field already exists, its value is replaced.
+    pub fn with_field(mut self, name: &str, value: &str) -> Self {
+        self.extra.set(name, value);
+        self
+    }
+
+    /// Resets the template's additional fields.
+    pub fn no_fields(mut self) -> Self {
+        self.extra = DebParagraph::new();
+        self
+    }
+
+    /// Returns the question's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the question's type.
+    pub fn template_type(&self) -> &DebTemplateType {
+        &self.template_type
+    }
+
+    /// Returns the question's default answer, if set.
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Returns the question's choices, if set.
+    pub fn choices(&self) -> Option<&str> {
+        self.choices.as_deref()
+    }
+
+    /// Returns the question's description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the template's additional fields in order.
+    pub fn fields(&self) -> &Vec<(String, String)> {
+        self.extra.fields()
+    }
+}
+
+impl fmt::Display for DebTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut paragraph = DebParagraph::new();
+        paragraph.set("Template", &self.name);
+        paragraph.set("Type", self.template_type.as_str());
+        if let Some(default) = &self.default {
+            paragraph.set("Default", default);
+        }
+        if let Some(choices) = &self.choices {
+            paragraph.set("Choices", choices);
+        }
+        paragraph.set("Description", &self.description);
+        for (name, value) in self.extra.fields() {
+            if paragraph.get(name).is_none() {
+                paragraph.set(name, value);
+            }
+        }
+        write!(f, "{}", paragraph.serialize())
+    }
+}
+
+// Returns the line number the template at `index` starts on in `input`
+fn template_line(input: &str, index: usize) -> usize {
+    let mut paragraph = 0;
+    let mut in_paragraph = false;
+    for (line_index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            in_paragraph = false;
+        } else if !in_paragraph && !line.starts_with('#') {
+            if paragraph == index {
+                return line_index + 1;
+            }
+            in_paragraph = true;
+            paragraph += 1;
+        }
+    }
+    1
+}
@@ -17,8 +17,8 @@
 */
 
 use std::fs;
-use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::io::{Error, Read, Write};
+use std::path::{Path, PathBuf};
 
 use crate::binary::*;
 use crate::*;
@@ -26,9 +26,9 @@ use crate::*;
 #[test]
 fn build_simple_package() -> std::io::Result<()> {
     DebPackage::new("test")
-        .set_version("0.1.0")
+        .set_version(DebVersion::from("0.1.0")?)
         .set_architecture(DebArchitecture::Amd64)
-        .with_depend("bash")
+        .with_depend(DebRelation::from("bash")?)
         .set_maintainer("NotSludgeBomb <notsludgebomb@protonmail.com>")
         .set_description("test package for deb-rust")
         .with_file(
@@ -45,27 +45,1659 @@ fn build_simple_package() -> std::io::Result<()> {
 
 #[test]
 fn read_simple_package() -> std::io::Result<()> {
-    let reader = DebPackage::from(fs::File::open("tester.deb")?)?;
+    let mut buf: Vec<u8> = Vec::new();
+    DebPackage::new("test")
+        .set_version(DebVersion::from("0.1.0")?)
+        .set_architecture(DebArchitecture::Amd64)
+        .with_depend(DebRelation::from("bash")?)
+        .set_maintainer("NotSludgeBomb <notsludgebomb@protonmail.com>")
+        .set_description("test package for deb-rust")
+        .build()?
+        .write(&mut buf)?;
+    let reader = DebPackage::from(buf.as_slice())?;
 
     let checks = [
         reader.name() == "test",
-        reader.version() == "0.1.0",
+        reader.version() == Some(&DebVersion::from("0.1.0")?),
         reader.priority() == &DebPriority::Optional,
         reader.architecture() == &DebArchitecture::Amd64,
         !reader.essential(),
-        reader.depends() == &vec!["bash".to_string()],
+        reader.depends() == &vec![DebRelation::new(DebDependency::new("bash"))],
         reader.maintainer() == "NotSludgeBomb <notsludgebomb@protonmail.com>",
         reader.description() == "test package for deb-rust",
     ];
 
     for i in checks {
         if !i {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "value of read field is incorrect",
-            ));
+            return Err(Error::other("value of read field is incorrect"));
         }
     }
 
     Ok(())
 }
+
+#[test]
+fn parse_multiline_control() -> std::io::Result<()> {
+    let control = "Package: hello\n\
+                   Version: 2.10-3\n\
+                   Architecture: amd64\n\
+                   Maintainer: Santiago Vila <sanvila@debian.org>\n\
+                   Depends: libc6 (>= 2.34)\n\
+                   Homepage: https://www.gnu.org/software/hello/\n\
+                   Description: example package based on GNU hello\n \
+                   The GNU hello program produces a familiar, friendly greeting.\n \
+                   .\n \
+                   Seriously, though: this is an example.\n";
+
+    let paragraph = DebParagraph::parse(control)?;
+    assert_eq!(
+        paragraph.get("homepage"),
+        Some("https://www.gnu.org/software/hello/")
+    );
+    assert_eq!(
+        paragraph.get("Description"),
+        Some(
+            "example package based on GNU hello\n \
+             The GNU hello program produces a familiar, friendly greeting.\n \
+             .\n \
+             Seriously, though: this is an example."
+        )
+    );
+    assert_eq!(paragraph.serialize(), control);
+
+    assert!(DebParagraph::parse(" orphaned continuation\n").is_err());
+    assert!(DebParagraph::parse("Package: a\nPackage: b\n").is_err());
+    assert_eq!(
+        DebParagraph::parse_all("A: 1\n\n\n# comment\nB: 2\n")?.len(),
+        2
+    );
+    Ok(())
+}
+
+#[test]
+fn preserve_additional_fields() -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    DebPackage::new("test")
+        .set_version(DebVersion::from("0.1.0")?)
+        .with_field("X-Build-Commit", "3f2a9c1")
+        .with_field("X-Team", "infrastructure")
+        .with_field("X-Notes", "first line\n  indented: line\n .\n last")
+        .build()?
+        .write(&mut buf)?;
+    let reader = DebPackage::from(buf.as_slice())?;
+
+    assert_eq!(reader.field("x-build-commit"), Some("3f2a9c1"));
+    assert_eq!(
+        reader.fields(),
+        &vec![
+            ("X-Build-Commit".to_string(), "3f2a9c1".to_string()),
+            ("X-Team".to_string(), "infrastructure".to_string()),
+            (
+                "X-Notes".to_string(),
+                "first line\n  indented: line\n .\n last".to_string()
+            ),
+        ]
+    );
+    assert_eq!(reader.without_field("X-Team").fields().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn parse_relations() -> std::io::Result<()> {
+    let relation = DebRelation::from(
+        "libc6 (>= 2.34) | libc6-compat:any [amd64 arm64] <!nocheck> <stage1 cross>",
+    )?;
+    let alternatives = relation.alternatives();
+    assert_eq!(alternatives.len(), 2);
+    assert_eq!(alternatives[0].name(), "libc6");
+    assert_eq!(
+        alternatives[0].version(),
+        Some(&(DebVersionOperator::LaterOrEqual, DebVersion::from("2.34")?))
+    );
+    assert_eq!(alternatives[1].arch_qualifier(), Some("any"));
+    assert_eq!(alternatives[1].architectures(), &vec!["amd64", "arm64"]);
+    assert_eq!(
+        alternatives[1].profiles(),
+        &vec![vec!["!nocheck"], vec!["stage1", "cross"]]
+    );
+    assert_eq!(
+        relation.to_string(),
+        "libc6 (>= 2.34) | libc6-compat:any [amd64 arm64] <!nocheck> <stage1 cross>"
+    );
+
+    let list = DebRelation::from_list("foo (<<1.0), bar,baz(=2)")?;
+    assert_eq!(list.len(), 3);
+    assert_eq!(list[0].to_string(), "foo (<< 1.0)");
+    assert_eq!(list[2].to_string(), "baz (= 2)");
+
+    for invalid in [
+        "",
+        "foo (~ 1.0)",
+        "foo (>= )",
+        "foo [amd64 !i386]",
+        "foo [amd64] (>= 1.0)",
+        "foo bar",
+        "foo | ",
+        "Foo-Bar",
+        "f",
+        "foo [AMD64]",
+    ] {
+        assert!(DebRelation::from(invalid).is_err(), "{}", invalid);
+    }
+    Ok(())
+}
+
+#[test]
+fn compare_versions() -> std::io::Result<()> {
+    // Each version is strictly earlier than the next, as sorted by dpkg
+    let ordered = [
+        "0~~", "0~", "0", "0a", "0+", "0.1~rc1", "0.1", "1.0~beta", "1.0", "1.0-1", "1.0-1a",
+        "1.0-1+b1", "1.0-1.1", "1.0-2", "1.0a", "1.0+dfsg", "1.2", "1.10", "9.99", "1:0.1",
+        "1:1.0", "2:0",
+    ];
+    for pair in ordered.windows(2) {
+        let (a, b) = (DebVersion::from(pair[0])?, DebVersion::from(pair[1])?);
+        assert!(a < b, "{} < {}", pair[0], pair[1]);
+    }
+
+    assert_eq!(DebVersion::from("1.0")?, DebVersion::from("0:1.0-0")?);
+    assert_eq!(DebVersion::from("1.001")?, DebVersion::from("1.1")?);
+    assert_eq!(DebVersion::from("1:2.3-4+b1")?.to_string(), "1:2.3-4+b1");
+    assert_eq!(DebVersion::from("2:1.0-2-3")?.upstream(), "1.0-2");
+
+    for invalid in [
+        "", "a1.0", "1.0-", "x:1.0", ":1.0", "1.0 beta", "1_0", "1.0-a_b",
+    ] {
+        assert!(DebVersion::from(invalid).is_err(), "{}", invalid);
+    }
+
+    let dependency = DebDependency::from("foo (>> 1.0~rc1)")?;
+    assert!(dependency.satisfied_by(&DebVersion::from("1.0")?));
+    assert!(!dependency.satisfied_by(&DebVersion::from("1.0~beta")?));
+    Ok(())
+}
+
+#[test]
+fn archive_control_fields() -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    DebPackage::new("test")
+        .set_version(DebVersion::from("0.1.0-1+b1")?)
+        .set_source("test-src")
+        .set_source_version(DebVersion::from("0.1.0-1")?)
+        .set_section("utils")
+        .set_multi_arch(DebMultiArch::Foreign)
+        .set_protected(true)
+        .with_tags(vec!["role::program", "interface::commandline"])
+        .with_file(DebFile::from_buf(vec![0; 1025], "/usr/bin/a"))
+        .with_file(DebFile::from_buf(vec![0; 10], "/usr/bin/b"))
+        .build()?
+        .write(&mut buf)?;
+    let reader = DebPackage::from(buf.as_slice())?;
+
+    assert_eq!(reader.source(), "test-src");
+    assert_eq!(reader.source_version(), Some(&DebVersion::from("0.1.0-1")?));
+    assert_eq!(reader.section(), "utils");
+    assert_eq!(reader.multi_arch(), Some(&DebMultiArch::Foreign));
+    assert!(reader.protected());
+    assert!(!reader.build_essential());
+    assert_eq!(reader.installed_size(), Some(3));
+    assert_eq!(
+        reader.tags(),
+        &vec!["role::program", "interface::commandline"]
+    );
+
+    // A size read from a package is recalculated, but a manually set one isn't
+    let mut buf: Vec<u8> = Vec::new();
+    reader
+        .clone()
+        .with_file(DebFile::from_buf(vec![0; 4096], "/usr/bin/c"))
+        .build()?
+        .write(&mut buf)?;
+    assert_eq!(DebPackage::from(buf.as_slice())?.installed_size(), Some(7));
+    let mut buf: Vec<u8> = Vec::new();
+    reader.set_installed_size(42).build()?.write(&mut buf)?;
+    assert_eq!(DebPackage::from(buf.as_slice())?.installed_size(), Some(42));
+    Ok(())
+}
+
+// Strategies for generating arbitrary packages, used to check that every
+// control field survives being built and read back
+mod round_trip {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn name() -> impl Strategy<Value = String> {
+        "[a-z0-9][a-z0-9+.-]{1,15}"
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        "[A-Za-z0-9][A-Za-z0-9 <>@.,:/_-]{0,30}[A-Za-z0-9>]"
+    }
+
+    fn version() -> impl Strategy<Value = DebVersion> {
+        (
+            0u32..3,
+            "[0-9][a-z0-9.+~]{0,8}",
+            proptest::option::of("[a-z0-9][a-z0-9.+~]{0,5}"),
+        )
+            .prop_map(|(epoch, upstream, revision)| {
+                let mut version = format!("{}:{}", epoch, upstream);
+                if let Some(revision) = revision {
+                    version = format!("{}-{}", version, revision);
+                }
+                DebVersion::from(&version).unwrap()
+            })
+    }
+
+    fn dependency() -> impl Strategy<Value = DebDependency> {
+        let operator = prop_oneof![
+            Just(DebVersionOperator::Earlier),
+            Just(DebVersionOperator::EarlierOrEqual),
+            Just(DebVersionOperator::Equal),
+            Just(DebVersionOperator::LaterOrEqual),
+            Just(DebVersionOperator::Later),
+        ];
+        (
+            name(),
+            proptest::option::of(prop_oneof![Just("any"), Just("native")]),
+            proptest::option::of((operator, version())),
+            proptest::collection::vec("[a-z0-9]{2,8}", 0..3),
+            any::<bool>(),
+            proptest::collection::vec(proptest::collection::vec("!?[a-z0-9]{2,8}", 1..3), 0..3),
+        )
+            .prop_map(
+                |(name, qualifier, version, architectures, negated, profiles)| {
+                    let mut dependency = DebDependency::new(&name);
+                    if let Some(qualifier) = qualifier {
+                        dependency = dependency.set_arch_qualifier(qualifier);
+                    }
+                    if let Some((operator, version)) = version {
+                        dependency = dependency.set_version(operator, version);
+                    }
+                    for architecture in architectures {
+                        dependency = match negated {
+                            true => dependency.with_architecture(&format!("!{}", architecture)),
+                            false => dependency.with_architecture(&architecture),
+                        };
+                    }
+                    for profile in profiles {
+                        dependency =
+                            dependency.with_profiles(profile.iter().map(|s| s.as_str()).collect());
+                    }
+                    dependency
+                },
+            )
+    }
+
+    fn relations() -> impl Strategy<Value = Vec<DebRelation>> {
+        let relation = proptest::collection::vec(dependency(), 1..3).prop_map(|alternatives| {
+            let mut alternatives = alternatives.into_iter();
+            let mut relation = DebRelation::new(alternatives.next().unwrap());
+            for alternative in alternatives {
+                relation = relation.or(alternative);
+            }
+            relation
+        });
+        proptest::collection::vec(relation, 0..3)
+    }
+
+    fn package() -> impl Strategy<Value = DebPackage> {
+        let metadata = (
+            name(),
+            proptest::option::of((name(), proptest::option::of(version()))),
+            proptest::option::of(version()),
+            proptest::option::of("[a-z]{2,10}(/[a-z]{2,10})?"),
+            proptest::sample::select(vec![
+                DebPriority::Required,
+                DebPriority::Important,
+                DebPriority::Standard,
+                DebPriority::Optional,
+                DebPriority::Extra,
+            ]),
+            proptest::sample::select(vec![
+                DebArchitecture::All,
+                DebArchitecture::Amd64,
+                DebArchitecture::Arm64,
+                DebArchitecture::HurdI386,
+            ]),
+            proptest::option::of(proptest::sample::select(vec![
+                DebMultiArch::Same,
+                DebMultiArch::Foreign,
+                DebMultiArch::Allowed,
+                DebMultiArch::No,
+            ])),
+            (any::<bool>(), any::<bool>(), any::<bool>()),
+            proptest::option::of(any::<u32>()),
+        );
+        let descriptive = (
+            proptest::option::of(text()),
+            (
+                proptest::option::of(text()),
+                proptest::collection::vec(proptest::option::of(text()), 0..4),
+            ),
+            proptest::option::of(text()),
+            proptest::collection::vec((name(), version()), 0..3),
+            proptest::collection::vec("[a-z]{1,8}::[a-z-]{1,8}", 0..3),
+            proptest::collection::vec(("X-[A-Za-z]{1,10}", text()), 0..3),
+        );
+        let relationships = [
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+            relations(),
+        ];
+        let contents = (
+            proptest::collection::vec(
+                (
+                    "/usr/share/[a-z]{1,10}",
+                    proptest::collection::vec(any::<u8>(), 0..2048),
+                    any::<bool>(),
+                ),
+                0..4,
+            ),
+            proptest::collection::vec(proptest::option::of(text()), 5),
+            any::<bool>(),
+        );
+
+        (metadata, descriptive, relationships, contents).prop_map(
+            |(metadata, descriptive, relationships, contents)| {
+                let (name, source, version, section, priority, architecture, multi_arch, flags, size) =
+                    metadata;
+                let (maintainer, description, homepage, built_using, tags, fields) = descriptive;
+                let (files, scripts, xz) = contents;
+
+                let mut package = DebPackage::new(&name)
+                    .set_priority(priority)
+                    .set_architecture(architecture)
+                    .set_essential(flags.0)
+                    .set_protected(flags.1)
+                    .set_build_essential(flags.2);
+                if let Some((source, source_version)) = source {
+                    package = package.set_source(&source);
+                    if let Some(source_version) = source_version {
+                        package = package.set_source_version(source_version);
+                    }
+                }
+                if let Some(version) = version {
+                    package = package.set_version(version);
+                }
+                if let Some(section) = section {
+                    package = package.set_section(&section);
+                }
+                if let Some(multi_arch) = multi_arch {
+                    package = package.set_multi_arch(multi_arch);
+                }
+                if let Some(size) = size {
+                    package = package.set_installed_size(size.into());
+                }
+                if let Some(maintainer) = maintainer {
+                    package = package.set_maintainer(&maintainer);
+                }
+                if let (Some(synopsis), lines) = description {
+                    let mut description = vec![synopsis];
+                    description.extend(lines.into_iter().map(Option::unwrap_or_default));
+                    package = package.set_description(&description.join("\n"));
+                }
+                if let Some(homepage) = homepage {
+                    package = package.set_homepage(&homepage);
+                }
+                for (using, version) in built_using {
+                    package = package.with_built_using(&using, version);
+                }
+                package = package.with_tags(tags.iter().map(|s| s.as_str()).collect());
+                for (name, value) in fields {
+                    package = package.with_field(&name, &value);
+                }
+
+                let [depends, pre_depends, recommends, suggests, breaks, conflicts, provides, replaces, enhances] =
+                    relationships;
+                package = package
+                    .with_depends(depends)
+                    .with_pre_depends(pre_depends)
+                    .with_recommends(recommends)
+                    .with_suggests(suggests)
+                    .with_breaks(breaks)
+                    .with_conflicts(conflicts)
+                    .with_provides(provides)
+                    .with_replaces(replaces)
+                    .with_enhances(enhances);
+
+                for (path, contents, exec) in files {
+                    let file = DebFile::from_buf(contents, path);
+                    package = package.with_file(match exec {
+                        true => file.is_exec(),
+                        false => file,
+                    });
+                }
+                let mut scripts = scripts.into_iter();
+                if let Some(script) = scripts.next().unwrap() {
+                    package = package.config_from_str(&script);
+                }
+                if let Some(script) = scripts.next().unwrap() {
+                    package = package.preinst_from_str(&script);
+                }
+                if let Some(script) = scripts.next().unwrap() {
+                    package = package.postinst_from_str(&script);
+                }
+                if let Some(script) = scripts.next().unwrap() {
+                    package = package.prerm_from_str(&script);
+                }
+                if let Some(script) = scripts.next().unwrap() {
+                    package = package.postrm_from_str(&script);
+                }
+                package.set_compression(match xz {
+                    true => DebCompression::Xz,
+                    false => DebCompression::Zstd,
+                })
+            },
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn build_and_read(package in package()) {
+            let mut buf: Vec<u8> = Vec::new();
+            package.build().unwrap().write(&mut buf).unwrap();
+            let reader = DebPackage::from(buf.as_slice()).unwrap();
+            prop_assert_eq!(reader, package);
+        }
+    }
+}
+
+#[test]
+fn mixed_compression() -> std::io::Result<()> {
+    let compressions = [
+        DebCompression::Xz,
+        DebCompression::Zstd,
+        DebCompression::Gzip,
+        DebCompression::Bzip2,
+        DebCompression::Lzma,
+        DebCompression::None,
+    ];
+    for control in compressions {
+        for data in compressions {
+            let package = DebPackage::new("test")
+                .set_control_compression(control)
+                .set_data_compression(data)
+                .with_file(DebFile::from_buf(vec![7; 4096], "/usr/share/test/data"));
+            // dpkg doesn't accept bzip2 or lzma compressed control archives
+            if !control.supports_control() {
+                assert!(matches!(
+                    package.build(),
+                    Err(DebError::UnsupportedCompression(name))
+                        if name == format!("control.tar{}", control.extension())
+                ));
+                assert!(package.build_to(std::io::Cursor::new(Vec::new())).is_err());
+                continue;
+            }
+            let mut buf: Vec<u8> = Vec::new();
+            package.build()?.write(&mut buf)?;
+
+            let archive = DebArchive::read(buf.as_slice())?;
+            assert_eq!(archive.control_compression(), &control);
+            assert_eq!(archive.data_compression(), &data);
+            assert_eq!(archive.to_package()?, package);
+        }
+    }
+
+    let package = DebPackage::new("test").set_compression(DebCompression::Bzip2);
+    assert_eq!(package.control_compression(), &DebCompression::Zstd);
+    assert_eq!(package.compression(), &DebCompression::Bzip2);
+
+    assert_eq!(
+        DebCompression::from_name("data.tar.lzma"),
+        Some(DebCompression::Lzma)
+    );
+    assert_eq!(
+        DebCompression::from_name("control.tar"),
+        Some(DebCompression::None)
+    );
+    assert_eq!(DebCompression::from_name("data.tar.lz4"), None);
+    Ok(())
+}
+
+#[test]
+fn compression_options() -> std::io::Result<()> {
+    let contents: Vec<u8> = (0..1 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let options = [
+        (
+            DebCompression::Xz,
+            DebCompressionOptions::new().set_level(6).set_extreme(true),
+        ),
+        (
+            DebCompression::Xz,
+            DebCompressionOptions::new().set_threads(4),
+        ),
+        (
+            DebCompression::Zstd,
+            DebCompressionOptions::new()
+                .set_level(19)
+                .set_threads(4)
+                .set_long_distance_matching(true)
+                .set_window_log(28),
+        ),
+        (
+            DebCompression::Gzip,
+            DebCompressionOptions::new().set_level(1),
+        ),
+        (
+            DebCompression::Bzip2,
+            DebCompressionOptions::new().set_level(1),
+        ),
+        (
+            DebCompression::Lzma,
+            DebCompressionOptions::new().set_level(0),
+        ),
+    ];
+    for (compression, options) in options {
+        let package = DebPackage::new("test")
+            .set_compression(compression)
+            .set_compression_options(options)
+            .with_file(DebFile::from_buf(contents.clone(), "/usr/share/test/data"));
+        let mut buf: Vec<u8> = Vec::new();
+        package.build()?.write(&mut buf)?;
+        // Zstd's window of 2^28 bytes is larger than readers allow by default
+        let limits = DebLimits::new().set_max_window_log(28);
+        assert_eq!(
+            DebArchive::read_with_limits(buf.as_slice(), limits)?.to_package()?,
+            package
+        );
+    }
+
+    // Compresses `input`, returning the compressed bytes
+    let compress = |compression: DebCompression, options: DebCompressionOptions, input: &[u8]| {
+        let mut output: Vec<u8> = Vec::new();
+        crate::compression::compress(compression, &options, input, &mut output).map(|_| output)
+    };
+    // Text-like data, random words from a small vocabulary, which compresses
+    // better the harder each format searches for matches
+    let mut seed: u32 = 1;
+    let mut random = move || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) as usize
+    };
+    let words: Vec<Vec<u8>> = (0..256)
+        .map(|_| {
+            (0..3 + random() % 6)
+                .map(|_| b'a' + (random() % 26) as u8)
+                .collect()
+        })
+        .collect();
+    let mut text: Vec<u8> = Vec::new();
+    while text.len() < 1 << 20 {
+        text.extend(&words[random() % words.len()]);
+        text.push(b' ');
+    }
+
+    // Each option changes the compressed output
+    for (compression, low, high) in [
+        (DebCompression::Xz, 0, 9),
+        (DebCompression::Zstd, 1, 19),
+        (DebCompression::Gzip, 1, 9),
+        (DebCompression::Bzip2, 1, 9),
+        (DebCompression::Lzma, 0, 9),
+    ] {
+        let low = compress(
+            compression,
+            DebCompressionOptions::new().set_level(low),
+            &text,
+        )?;
+        let high = compress(
+            compression,
+            DebCompressionOptions::new().set_level(high),
+            &text,
+        )?;
+        assert!(high.len() < low.len(), "{:?}", compression);
+    }
+    let xz = DebCompressionOptions::new().set_level(6);
+    assert_ne!(
+        compress(DebCompression::Xz, xz, &text)?,
+        compress(DebCompression::Xz, xz.set_extreme(true), &text)?
+    );
+    assert_ne!(
+        compress(DebCompression::Xz, xz, &text)?,
+        compress(DebCompression::Xz, xz.set_threads(4), &text)?
+    );
+
+    // Long distance matching finds repeats further apart than the default window
+    let repeated = [text.as_slice(), text.as_slice()].concat();
+    let zstd = DebCompressionOptions::new().set_level(1);
+    assert!(
+        compress(
+            DebCompression::Zstd,
+            zstd.set_long_distance_matching(true),
+            &repeated
+        )?
+        .len()
+            < compress(DebCompression::Zstd, zstd, &repeated)?.len() * 3 / 4
+    );
+    // The frame header's window descriptor holds the window log, less 10, in
+    // its top five bits
+    let framed = compress(DebCompression::Zstd, zstd.set_window_log(28), &text)?;
+    assert_eq!(framed[4] & 0x20, 0);
+    assert_eq!(framed[5] >> 3, 28 - 10);
+
+    for (compression, level) in [
+        (DebCompression::Xz, 10),
+        (DebCompression::Zstd, 23),
+        (DebCompression::Gzip, 10),
+        (DebCompression::Bzip2, 0),
+        (DebCompression::Lzma, -1),
+    ] {
+        assert!(matches!(
+            compress(compression, DebCompressionOptions::new().set_level(level), &text),
+            Err(DebError::InvalidCompressionLevel(invalid)) if invalid == level
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn streaming_build() -> std::io::Result<()> {
+    use std::io::{Cursor, Read};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let package = DebPackage::new("test")
+        .with_file(DebFile::from_reader(
+            || Ok(std::io::repeat(7).take(3 << 20)),
+            "/usr/share/test/large",
+        ))
+        .with_file(DebFile::from_buf(
+            b"small".to_vec(),
+            "/usr/share/test/small",
+        ));
+
+    // Streaming a package produces the same package as building it in memory
+    let mut streamed = Cursor::new(Vec::new());
+    package.build_to(&mut streamed)?;
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+    let read = DebPackage::from(streamed.get_ref().as_slice())?;
+    assert_eq!(read, DebPackage::from(buf.as_slice())?);
+
+    assert_eq!(read.installed_size(), Some(3073));
+    assert_eq!(read.files()[0].read_contents()?, vec![7; 3 << 20]);
+    assert_eq!(read.files()[1].contents(), Some(&b"small".to_vec()));
+
+    // Files which change between being measured and written are rejected
+    let reads = AtomicU64::new(0);
+    let package = DebPackage::new("test").with_file(DebFile::from_reader(
+        move || Ok(std::io::repeat(0).take(1024 + reads.fetch_add(1, Ordering::SeqCst))),
+        "/usr/share/test/growing",
+    ));
+    assert!(package.build_to(Cursor::new(Vec::new())).is_err());
+
+    // Members whose name or size doesn't fit in an ar header are errors rather
+    // than corrupt archives
+    let mut header: Vec<u8> = Vec::new();
+    write_ar_header(&mut header, "data.tar.xz", 9_999_999_999)?;
+    assert_eq!(header.len(), 60);
+    assert!(matches!(
+        write_ar_header(&mut header, "data.tar.xz", 10_000_000_000),
+        Err(DebError::ArHeaderOverflow(_))
+    ));
+    assert!(matches!(
+        write_ar_header(&mut header, "_gpgbuilder-extra", 0),
+        Err(DebError::ArHeaderOverflow(_))
+    ));
+    assert_eq!(header.len(), 60);
+    Ok(())
+}
+
+#[test]
+fn lazy_reader() -> std::io::Result<()> {
+    use std::io::Read;
+
+    let package = DebPackage::new("test")
+        .set_version(DebVersion::from("1.0")?)
+        .postinst_from_str("#!/bin/sh\necho installed")
+        .with_file(DebFile::from_buf(vec![1; 4096], "/usr/share/test/first"))
+        .with_file(DebFile::from_buf(b"second".to_vec(), "/usr/share/test/second").is_exec());
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+
+    let mut reader = DebReader::new(buf.as_slice())?;
+    assert_eq!(reader.package().name(), "test");
+    assert_eq!(reader.package().version(), package.version());
+    assert_eq!(reader.package().postinst(), package.postinst());
+    assert!(reader.package().files().is_empty());
+
+    let mut entries = reader.entries()?;
+    let first = entries.next().unwrap()?;
+    assert_eq!(first.path()?, PathBuf::from("/usr/share/test/first"));
+    assert_eq!(first.size(), 4096);
+    drop(first);
+
+    let mut second = entries.next().unwrap()?;
+    assert_eq!(second.path()?, PathBuf::from("/usr/share/test/second"));
+    assert_eq!(second.mode()?, 33261);
+    let mut contents = String::new();
+    second.read_to_string(&mut contents)?;
+    assert_eq!(contents, "second");
+    assert!(entries.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn link_and_dir_entries() -> std::io::Result<()> {
+    let package = DebPackage::new("test")
+        .with_file(DebFile::new_dir("/usr/lib/test").set_mode(16893))
+        .with_file(DebFile::from_buf(
+            vec![0; 2048],
+            "/usr/lib/test/libtest.so.1",
+        ))
+        .with_file(DebFile::new_symlink(
+            "libtest.so.1",
+            "/usr/lib/test/libtest.so",
+        ))
+        .with_file(DebFile::new_hardlink(
+            "/usr/lib/test/libtest.so.1",
+            "/usr/lib/test/libtest.so.1.0",
+        ));
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+
+    let read = DebPackage::from(buf.as_slice())?;
+    assert_eq!(read, package);
+    assert_eq!(read.files()[0].kind(), &DebFileKind::Dir);
+    assert_eq!(read.files()[0].mode(), &16893);
+    assert_eq!(
+        read.files()[2].kind(),
+        &DebFileKind::Symlink(PathBuf::from("libtest.so.1"))
+    );
+    assert_eq!(
+        read.files()[3].kind(),
+        &DebFileKind::Hardlink(PathBuf::from("/usr/lib/test/libtest.so.1"))
+    );
+    // Two kibibytes for the file, and one each for the directory and symlink
+    assert_eq!(read.installed_size(), Some(4));
+    Ok(())
+}
+
+#[test]
+fn file_ownership() -> std::io::Result<()> {
+    let package = DebPackage::new("test")
+        .with_file(DebFile::from_buf(b"test".to_vec(), "/usr/bin/test"))
+        .with_file(
+            DebFile::new_dir("/var/lib/test")
+                .set_owner(120, "test")
+                .set_group(130, "test"),
+        );
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+
+    let read = DebPackage::from(buf.as_slice())?;
+    assert_eq!(read, package);
+    assert_eq!(read.files()[0].uid(), 0);
+    assert_eq!(read.files()[0].uname(), "root");
+    assert_eq!(read.files()[0].gname(), "root");
+    assert_eq!(read.files()[1].uid(), 120);
+    assert_eq!(read.files()[1].gid(), 130);
+    assert_eq!(read.files()[1].uname(), "test");
+    assert_eq!(read.files()[1].gname(), "test");
+    Ok(())
+}
+
+#[test]
+fn conffiles() -> std::io::Result<()> {
+    let package = DebPackage::new("test")
+        .set_etc_conffiles(true)
+        .with_file(DebFile::from_buf(b"a=1".to_vec(), "/etc/test/a.conf"))
+        .with_conffile("/usr/share/test/b.conf")
+        .with_file(DebFile::from_buf(b"b=1".to_vec(), "/usr/share/test/b.conf"))
+        .with_file(DebFile::from_buf(b"test".to_vec(), "/usr/bin/test"))
+        .with_removed_conffile("/etc/test/old.conf");
+    assert!(package.files()[1].conffile());
+    assert!(!package.files()[2].conffile());
+
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+    let read = DebPackage::from(buf.as_slice())?;
+    assert_eq!(read, package);
+    assert_eq!(
+        read.conffiles(),
+        vec![
+            DebConffile::new("/etc/test/a.conf"),
+            DebConffile::new("/usr/share/test/b.conf"),
+            DebConffile::new("/etc/test/old.conf").set_remove_on_upgrade(true),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn control_members() -> std::io::Result<()> {
+    let package = DebPackage::new("libtest1")
+        .with_trigger(DebTrigger::new(
+            DebTriggerDirective::ActivateNoawait,
+            "ldconfig",
+        ))
+        .with_trigger(DebTrigger::from("interest /usr/share/test")?)
+        .with_shlib(
+            DebShlib::new("libtest", "1").with_dependency(DebRelation::from("libtest1 (>= 1.2)")?),
+        )
+        .with_shlib(DebShlib::from("udeb: libtest 1 libtest1-udeb")?)
+        .with_symbols(
+            DebSymbols::new("libtest.so.1", "libtest1 #MINVER#")
+                .with_alternative("libtest1-alt #MINVER#")
+                .with_field("Build-Depends-Package", "libtest-dev")
+                .with_symbol(DebSymbol::new("test_init@Base", DebVersion::from("1.0")?))
+                .with_symbol(DebSymbol::from("(c++)\"test::run(int)@Base\" 1.2~rc1 1")?),
+        )
+        .with_template(
+            DebTemplate::new("libtest1/enable", DebTemplateType::Boolean)
+                .set_default("true")
+                .set_description("Enable test?\nThis enables test.\n\nReally.")
+                .with_field("Description-de.UTF-8", "Test aktivieren?"),
+        )
+        .with_template(
+            DebTemplate::new("libtest1/mode", DebTemplateType::Select)
+                .set_choices("fast, slow")
+                .set_description("Test mode:"),
+        )
+        .with_control_file(DebFile::from_buf(b"custom".to_vec(), "custom"));
+    let mut buf: Vec<u8> = Vec::new();
+    package.build()?.write(&mut buf)?;
+
+    let read = DebPackage::from(buf.as_slice())?;
+    assert_eq!(read, package);
+    assert_eq!(
+        read.symbols()[0].symbols()[1].name(),
+        "(c++)\"test::run(int)@Base\""
+    );
+    assert_eq!(read.symbols()[0].symbols()[1].dependency(), Some(1));
+    assert_eq!(read.shlibs()[1].package_type(), Some("udeb"));
+    assert_eq!(read.templates()[1].choices(), Some("fast, slow"));
+    assert_eq!(read.control_files()[0].path(), &PathBuf::from("custom"));
+    Ok(())
+}
+
+#[test]
+fn verify_package() -> std::io::Result<()> {
+    let archive = DebPackage::new("test")
+        .with_file(DebFile::from_buf(b"one".to_vec(), "/usr/share/test/one"))
+        .with_file(DebFile::from_buf(b"two".to_vec(), "/usr/share/test/two"))
+        .with_file(DebFile::new_symlink("one", "/usr/share/test/link"))
+        .build()?;
+    let mut buf: Vec<u8> = Vec::new();
+    archive.write(&mut buf)?;
+
+    let read = DebPackage::from(buf.as_slice())?;
+    assert!(read.verify()?.is_ok());
+    assert!(DebPackage::new("test").verify().is_err());
+
+    let tampered = read
+        .clear_files()
+        .with_file(DebFile::from_buf(
+            b"changed".to_vec(),
+            "/usr/share/test/one",
+        ))
+        .with_file(DebFile::from_buf(
+            b"three".to_vec(),
+            "/usr/share/test/three",
+        ))
+        .verify()?;
+    assert!(!tampered.is_ok());
+    assert_eq!(
+        tampered.mismatched(),
+        &vec![PathBuf::from("/usr/share/test/one")]
+    );
+    assert_eq!(
+        tampered.missing(),
+        &vec![PathBuf::from("/usr/share/test/two")]
+    );
+    assert_eq!(
+        tampered.extra(),
+        &vec![PathBuf::from("/usr/share/test/three")]
+    );
+
+    let digests = archive.digests()?;
+    assert_eq!(digests, DebDigests::from_buf(&buf));
+    assert_eq!(digests.size(), buf.len() as u64);
+    assert!(archive.control_digests().size() + archive.data_digests().size() < buf.len() as u64);
+
+    let digests = DebDigests::from_reader(b"abc".as_slice())?;
+    assert_eq!(digests.md5(), "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(digests.sha1(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        digests.sha256(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(&digests.sha512()[..16], "ddaf35a193617aba");
+    Ok(())
+}
+
+#[test]
+fn reproducible_build() -> std::io::Result<()> {
+    let build = |first: DebFile, second: DebFile| -> std::io::Result<Vec<u8>> {
+        let mut buf: Vec<u8> = Vec::new();
+        DebPackage::new("test")
+            .set_reproducible(true)
+            .set_source_date_epoch(1_700_000_000)
+            .postinst_from_str("#!/bin/sh")
+            .with_file(first)
+            .with_file(second)
+            .with_file(DebFile::new_dir("/usr/share/test"))
+            .build()?
+            .write(&mut buf)?;
+        Ok(buf)
+    };
+    let a = || DebFile::from_buf(b"a".to_vec(), "/usr/share/test/a");
+    let b = || DebFile::from_buf(b"b".to_vec(), "/usr/share/test/b");
+
+    // Insertion order, ownership and modification times after the epoch don't matter
+    let first = build(a(), b().set_mtime(1_800_000_000))?;
+    let second = build(
+        b().set_mtime(1_900_000_000)
+            .set_owner(1000, "builder")
+            .set_group(1000, "builder"),
+        a(),
+    )?;
+    assert_eq!(first, second);
+
+    let read = DebPackage::from(second.as_slice())?;
+    assert_eq!(read.files()[0].path(), &PathBuf::from("/usr/share/test"));
+    assert_eq!(read.files()[1].path(), &PathBuf::from("/usr/share/test/a"));
+    assert_eq!(read.files()[1].mtime(), 1_700_000_000);
+    for file in read.files() {
+        assert_eq!((file.uid(), file.uname()), (0, "root"));
+        assert_eq!((file.gid(), file.gname()), (0, "root"));
+    }
+
+    // Earlier modification times are kept
+    let read = DebPackage::from(build(a().set_mtime(1000), b())?.as_slice())?;
+    assert_eq!(read.files()[1].mtime(), 1000);
+    Ok(())
+}
+
+#[test]
+fn typed_errors() {
+    assert!(matches!(
+        DebArchitecture::from("bogus arch"),
+        Err(DebError::UnknownArchitecture(name)) if name == "bogus arch"
+    ));
+    assert!(matches!(
+        DebVersion::from("1.0:a"),
+        Err(DebError::InvalidVersion { version, .. }) if version == "1.0:a"
+    ));
+    assert!(matches!(
+        DebParagraph::parse("Package: test\n continued\nbroken"),
+        Err(DebError::InvalidSyntax { line: 3, .. })
+    ));
+    assert!(matches!(
+        DebReader::new(b"not a deb".as_slice()),
+        Err(DebError::MalformedAr(_))
+    ));
+    assert!(matches!(
+        DebPackage::from_control("Package: test\nBuilt-Using: gcc-12 (>= 12.2)\n"),
+        Err(DebError::InvalidControlField { field, line: 2, .. }) if field == "Built-Using"
+    ));
+
+    // Errors survive a round trip through std::io::Error
+    let error: std::io::Error = DebError::MissingMd5sums.into();
+    assert!(matches!(DebError::from(error), DebError::MissingMd5sums));
+}
+
+#[test]
+fn ar_members() -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    DebPackage::new("test").build()?.write(&mut buf)?;
+
+    // Additional members are kept and written back in place
+    let mut signed = buf.clone();
+    signed.extend(
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}`\n",
+            "_gpgorigin", 0, 0, 0, 33188, 3
+        )
+        .as_bytes(),
+    );
+    signed.extend(b"sig\n");
+    let archive = DebArchive::read(signed.as_slice())?;
+    assert_eq!(
+        archive.extra_members(),
+        &vec![("_gpgorigin".to_string(), b"sig".to_vec())]
+    );
+    let mut written: Vec<u8> = Vec::new();
+    archive.write(&mut written)?;
+    assert_eq!(written, signed);
+    DebReader::new(signed.as_slice())?;
+
+    // So are members before the control archive, and minor format versions
+    let mut reordered = buf.clone();
+    reordered[70] = b'1';
+    reordered.splice(72..72, signed[buf.len()..].iter().copied());
+    let archive = DebArchive::read(reordered.as_slice())?;
+    let mut written: Vec<u8> = Vec::new();
+    archive.write(&mut written)?;
+    assert_eq!(written, reordered);
+
+    // Malformed archives are errors rather than panics
+    assert!(matches!(
+        DebArchive::read([].as_slice()),
+        Err(DebError::MalformedAr(_))
+    ));
+    assert!(matches!(
+        DebArchive::read(&buf[..100]),
+        Err(DebError::MalformedAr(_))
+    ));
+    let mut version = buf.clone();
+    version[68] = b'3';
+    assert!(matches!(
+        DebArchive::read(version.as_slice()),
+        Err(DebError::UnsupportedFormat(v)) if v == "3.0"
+    ));
+    let mut unknown = buf.clone();
+    unknown[72..79].copy_from_slice(b"unknown");
+    assert!(matches!(
+        DebArchive::read(unknown.as_slice()),
+        Err(DebError::MalformedAr(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn read_limits() -> std::io::Result<()> {
+    let mut buf: Vec<u8> = Vec::new();
+    DebPackage::new("test")
+        .set_data_compression(DebCompression::None)
+        .with_file(DebFile::from_buf(vec![0; 8192], "/usr/share/test/a"))
+        .with_file(DebFile::from_buf(vec![0; 8192], "/usr/share/test/b"))
+        .with_file(DebFile::from_buf(b"root".to_vec(), "/usr/ </passwd"))
+        .build()?
+        .write(&mut buf)?;
+
+    let read = |limits: DebLimits| DebArchive::read_with_limits(buf.as_slice(), limits);
+    assert!(matches!(
+        read(DebLimits::new().set_max_member_size(1024)),
+        Err(DebError::LimitExceeded(_))
+    ));
+    assert!(matches!(
+        read(DebLimits::new().set_max_entries(2))?.to_package(),
+        Err(DebError::LimitExceeded(_))
+    ));
+    assert!(matches!(
+        read(DebLimits::new().set_max_decompressed_size(4096))?.to_package(),
+        Err(DebError::LimitExceeded(_))
+    ));
+    {
+        // The control archive's two entries count towards the limit too
+        assert!(matches!(
+            DebReader::with_limits(buf.as_slice(), DebLimits::new().set_max_entries(1)),
+            Err(DebError::LimitExceeded(_))
+        ));
+        let mut reader =
+            DebReader::with_limits(buf.as_slice(), DebLimits::new().set_max_entries(2))?;
+        assert!(reader.entries()?.any(|entry| entry.is_err()));
+    }
+
+    // Decompressors can't use windows larger than the limit, however little
+    // data they're given
+    for (compression, options, window_log) in [
+        (
+            DebCompression::Zstd,
+            DebCompressionOptions::new().set_window_log(28),
+            28,
+        ),
+        (DebCompression::Xz, DebCompressionOptions::new(), 26),
+        (DebCompression::Lzma, DebCompressionOptions::new(), 26),
+    ] {
+        let mut buf: Vec<u8> = Vec::new();
+        DebPackage::new("test")
+            .set_data_compression(compression)
+            .set_compression_options(options)
+            .build()?
+            .write(&mut buf)?;
+        let read = |window_log: u32| {
+            let limits = DebLimits::new().set_max_window_log(window_log);
+            DebArchive::read_with_limits(buf.as_slice(), limits)?.to_package()
+        };
+        assert!(read(window_log - 1).is_err());
+        read(window_log)?;
+    }
+
+    // Swapping " <" for "..", which keeps the tar checksum valid, makes the
+    // last file escape the root directory
+    let position = buf
+        .windows(6)
+        .position(|window| window == b"usr/ <")
+        .unwrap();
+    buf[position + 4..position + 6].copy_from_slice(b"..");
+    assert!(matches!(
+        DebArchive::read(buf.as_slice())?.to_package(),
+        Err(DebError::UnsafePath(path)) if path == Path::new("usr/../passwd")
+    ));
+
+    // So do entries and hardlinks inside a symlink from the same archive
+    for file in [
+        DebFile::from_buf(b"root".to_vec(), "/usr/x/passwd"),
+        DebFile::new_hardlink("/usr/x/passwd", "/usr/passwd"),
+    ] {
+        let mut buf: Vec<u8> = Vec::new();
+        DebPackage::new("test")
+            .with_file(DebFile::new_symlink("../../../../etc", "/usr/x"))
+            .with_file(file)
+            .build()?
+            .write(&mut buf)?;
+        assert!(matches!(
+            DebArchive::read(buf.as_slice())?.to_package(),
+            Err(DebError::UnsafePath(path)) if path == Path::new("/usr/x/passwd")
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn architecture_wildcards() -> std::io::Result<()> {
+    assert_eq!(DebArchitecture::from("powerpc")?, DebArchitecture::PowerPC);
+    assert_eq!(DebArchitecture::Sparc64.as_str(), "sparc64");
+    assert_eq!(
+        DebArchitecture::from("musl-linux-armhf")?,
+        DebArchitecture::Custom("musl-linux-armhf".to_string())
+    );
+    assert_eq!(
+        DebArchitecture::from("musl-linux-armhf")?.tuple(),
+        Some(["eabihf", "musl", "linux", "arm"].map(String::from))
+    );
+    assert_eq!(
+        DebArchitecture::HurdI386.tuple(),
+        Some(["base", "gnu", "hurd", "i386"].map(String::from))
+    );
+    assert_eq!(DebArchitecture::All.tuple(), None);
+    assert_eq!(
+        DebArchitecture::from("armeb")?.tuple(),
+        Some(["eabi", "gnu", "linux", "armeb"].map(String::from))
+    );
+    assert_eq!(
+        DebArchitecture::from("arm64ilp32")?.tuple(),
+        Some(["ilp32", "gnu", "linux", "arm64"].map(String::from))
+    );
+    assert_eq!(DebArchitecture::from("any")?, DebArchitecture::Any);
+    for wildcard in ["linux-any", "any-amd64", "musl-any-any"] {
+        assert!(matches!(
+            DebArchitecture::from(wildcard),
+            Err(DebError::UnknownArchitecture(name)) if name == wildcard
+        ));
+    }
+
+    let matches = |wildcard: &str, architecture: DebArchitecture| -> std::io::Result<bool> {
+        Ok(DebArchitectureWildcard::from(wildcard)?.matches(&architecture))
+    };
+    assert!(matches("any", DebArchitecture::Riscv64)?);
+    assert!(matches("linux-any", DebArchitecture::Amd64)?);
+    assert!(!matches("linux-any", DebArchitecture::KFreebsdAmd64)?);
+    assert!(matches("any-amd64", DebArchitecture::KFreebsdAmd64)?);
+    assert!(matches("any-amd64", DebArchitecture::X32)?);
+    assert!(!matches("any-amd64", DebArchitecture::I386)?);
+    assert!(matches("any-arm", DebArchitecture::Armhf)?);
+    assert!(matches(
+        "musl-any-any",
+        DebArchitecture::from("musl-linux-arm64")?
+    )?);
+    assert!(!matches("musl-any-any", DebArchitecture::Arm64)?);
+    assert!(matches("armhf", DebArchitecture::Armhf)?);
+    assert!(!matches("armhf", DebArchitecture::Armel)?);
+
+    let relations = DebRelation::from_list("libfoo [linux-any], libbar [!any-i386]")?;
+    assert!(relations[0].alternatives()[0].applies_to(&DebArchitecture::Arm64));
+    assert!(!relations[0].alternatives()[0].applies_to(&DebArchitecture::HurdI386));
+    assert!(!relations[1].alternatives()[0].applies_to(&DebArchitecture::HurdI386));
+    Ok(())
+}
+
+// Returns an empty directory for a test, unique to this run so that
+// concurrent runs don't share it and failed runs don't leave stale files behind
+fn test_dir(name: &str) -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("deb-rust-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+#[test]
+fn packages_index() -> std::io::Result<()> {
+    let root = test_dir("packages-index")?;
+    let pool = root.join("pool/main/t/test");
+    fs::create_dir_all(&pool)?;
+
+    let package = DebPackage::new("test")
+        .set_version(DebVersion::from("1.0")?)
+        .set_architecture(DebArchitecture::Amd64)
+        .set_description("test package\n with a long description")
+        .set_reproducible(true)
+        .with_file(DebFile::from_buf(vec![0; 2048], "/usr/share/test/data"));
+    let archive = package.build()?;
+    let mut buf: Vec<u8> = Vec::new();
+    archive.write(&mut buf)?;
+    fs::write(pool.join("test_1.0_amd64.deb"), &buf)?;
+
+    let filename = "pool/main/t/test/test_1.0_amd64.deb";
+    let index = repository::DebPackagesIndex::from_pool(&root, "pool")?;
+    assert_eq!(
+        index,
+        repository::DebPackagesIndex::new().with_archive(&archive, filename)?
+    );
+    let stanza = &index.stanzas()[0];
+    assert_eq!(stanza.get("Package"), Some("test"));
+    assert_eq!(stanza.get("Installed-Size"), Some("2"));
+    assert_eq!(stanza.get("Filename"), Some(filename));
+    assert_eq!(stanza.get("Size"), Some(buf.len().to_string().as_str()));
+    assert_eq!(
+        stanza.get("SHA256"),
+        Some(DebDigests::from_buf(&buf).sha256())
+    );
+    assert_eq!(
+        stanza.get("Description-md5"),
+        Some(
+            format!(
+                "{:x}",
+                md5::compute("test package\n with a long description\n")
+            )
+            .as_str()
+        )
+    );
+
+    // Compressed indices decompress to the same contents
+    index.write_to_dir(&root, &[DebCompression::None, DebCompression::Gzip])?;
+    let mut decompressed = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(root.join("Packages.gz"))?)
+        .read_to_string(&mut decompressed)?;
+    assert_eq!(decompressed, fs::read_to_string(root.join("Packages"))?);
+    assert_eq!(decompressed, index.serialize());
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+#[test]
+fn release_file() -> std::io::Result<()> {
+    let dist = test_dir("release-file")?;
+    let binary = dist.join("main/binary-amd64");
+    fs::create_dir_all(&binary)?;
+    repository::DebPackagesIndex::new()
+        .with_package(&DebPackage::new("test"), "pool/main/t/test/test_amd64.deb")?
+        .write_to_dir(&binary, &[DebCompression::None, DebCompression::Xz])?;
+
+    let release = repository::DebRelease::new("stable")
+        .set_origin("Test")
+        .set_codename("test")
+        .set_date(0)
+        .set_valid_until(86400)
+        .with_architecture(DebArchitecture::Amd64)
+        .with_architecture(DebArchitecture::Arm64)
+        .with_component("main")
+        .set_description("Test suite\n\nFor testing only")
+        .set_acquire_by_hash(true)
+        .with_dir(&dist)?;
+    release.write_to_dir(&dist)?;
+    // Files written by earlier releases are ignored
+    assert_eq!(release, release.clone().no_files().with_dir(&dist)?);
+
+    let packages = DebDigests::from_reader(fs::File::open(binary.join("Packages"))?)?;
+    let paragraph = DebParagraph::parse(&fs::read_to_string(dist.join("Release"))?)?;
+    assert_eq!(paragraph.get("Suite"), Some("stable"));
+    assert_eq!(paragraph.get("Date"), Some("Thu, 01 Jan 1970 00:00:00 UTC"));
+    assert_eq!(
+        paragraph.get("Valid-Until"),
+        Some("Fri, 02 Jan 1970 00:00:00 UTC")
+    );
+    assert_eq!(paragraph.get("Architectures"), Some("amd64 arm64"));
+    assert_eq!(paragraph.get("Acquire-By-Hash"), Some("yes"));
+    assert_eq!(paragraph.get("Description"), Some(release.description()));
+    assert_eq!(release.description(), "Test suite\n .\n For testing only");
+    assert_eq!(
+        paragraph.get("SHA256").unwrap().lines().nth(1),
+        Some(
+            format!(
+                " {} {:>16} main/binary-amd64/Packages",
+                packages.sha256(),
+                packages.size()
+            )
+            .as_str()
+        )
+    );
+    assert_eq!(
+        fs::read(binary.join("by-hash/SHA256").join(packages.sha256()))?,
+        fs::read(binary.join("Packages"))?
+    );
+
+    // Files which changed after they were added aren't published by hash
+    let stale = release.clone().with_dir(&dist)?;
+    fs::write(binary.join("Packages"), "changed\n")?;
+    let changed = DebDigests::from_buf(b"changed\n");
+    assert!(matches!(
+        stale.write_to_dir(&dist),
+        Err(DebError::FileChanged(path)) if path == binary.join("Packages")
+    ));
+    assert!(!binary
+        .join("by-hash/SHA256")
+        .join(changed.sha256())
+        .exists());
+
+    // Paths can't leave the suite's directory
+    for path in ["../Release", "/etc/passwd", "main/../../Release"] {
+        assert!(matches!(
+            release.clone().with_file(path, changed.clone()),
+            Err(DebError::UnsafePath(_))
+        ));
+    }
+
+    fs::remove_dir_all(&dist)?;
+    Ok(())
+}
+
+#[test]
+fn parse_indices() -> std::io::Result<()> {
+    let stanza = |name: &str, version: &str, architecture: &str| {
+        format!(
+            "Package: {}\nVersion: {}\nArchitecture: {}\nDepends: libc6 (>= 2.34)\n\
+             Filename: pool/main/{}_{}_{}.deb\nSize: 1024\nSHA256: abc\n",
+            name, version, architecture, name, version, architecture
+        )
+    };
+    let packages = [
+        stanza("foo", "1.0", "amd64"),
+        stanza("foo", "1.10", "amd64"),
+        stanza("foo", "1.2", "arm64"),
+        stanza("bar", "2.0", "all"),
+        stanza("foo", "1.9", "amd64"),
+    ]
+    .join("\n");
+    let mut compressed = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    compressed.write_all(packages.as_bytes())?;
+    let index = repository::DebPackagesIndex::read(compressed.finish()?.as_slice())?;
+    assert_eq!(index, repository::DebPackagesIndex::parse(&packages)?);
+
+    assert_eq!(index.packages().len(), 5);
+    assert_eq!(index.find("foo").len(), 4);
+    let foo = index
+        .get("foo", &DebVersion::from("1.2")?, &DebArchitecture::Arm64)
+        .unwrap();
+    assert_eq!(foo.filename(), "pool/main/foo_1.2_arm64.deb");
+    assert_eq!(foo.size(), 1024);
+    assert_eq!(foo.sha256(), "abc");
+    assert_eq!(foo.package().depends()[0].to_string(), "libc6 (>= 2.34)");
+    let latest: Vec<String> = index
+        .latest()
+        .iter()
+        .map(|package| package.filename().to_string())
+        .collect();
+    assert_eq!(
+        latest,
+        [
+            "pool/main/foo_1.10_amd64.deb",
+            "pool/main/foo_1.2_arm64.deb",
+            "pool/main/bar_2.0_all.deb"
+        ]
+    );
+
+    // Errors point at the line in the whole index
+    assert!(matches!(
+        repository::DebPackagesIndex::parse(&format!("{}\n{}", packages, "Package: baz\nSize: 1\n")),
+        Err(DebError::InvalidControlField { field, line: 41, .. }) if field == "Filename"
+    ));
+    assert!(matches!(
+        repository::DebPackagesIndex::parse(&packages.replace("Version: 1.9", "Version: 1:")),
+        Err(DebError::InvalidControlField { field, line: 34, .. }) if field == "Version"
+    ));
+
+    let sources = repository::DebSourcesIndex::parse(
+        "Package: foo\nBinary: foo, libfoo1\nVersion: 1.0-1\nArchitecture: any all\n\
+         Build-Depends: debhelper-compat (= 13)\nDirectory: pool/main/f/foo\n\
+         Files:\n 0123 100 foo_1.0-1.dsc\n 4567 2000 foo_1.0.orig.tar.xz\n\
+         Checksums-Sha256:\n 89ab 100 foo_1.0-1.dsc\n cdef 2000 foo_1.0.orig.tar.xz\n\n\
+         Package: foo\nVersion: 1.1-1\n",
+    )?;
+    assert_eq!(sources.sources().len(), 2);
+    assert_eq!(sources.latest()[0].version(), &DebVersion::from("1.1-1")?);
+    let foo = sources.get("foo", &DebVersion::from("1.0-1")?).unwrap();
+    assert_eq!(foo.binaries(), &vec!["foo", "libfoo1"]);
+    assert!(foo.architectures()[0].matches(&DebArchitecture::Riscv64));
+    assert_eq!(foo.build_depends().len(), 1);
+    assert_eq!(foo.files()[1].name(), "foo_1.0.orig.tar.xz");
+    assert_eq!(foo.files()[1].size(), 2000);
+    assert_eq!(foo.files()[1].md5(), "4567");
+    assert_eq!(foo.files()[1].sha256(), "cdef");
+    Ok(())
+}
+
+#[test]
+fn openpgp_signatures() -> std::io::Result<()> {
+    let key = DebSigningKey::from_seed([7; 32], "Test <test@example.com>", 1700000000);
+    assert_eq!(
+        key.public_key().fingerprint(),
+        "342C25FD3FE71DF8FDCA402B8DFC5FCE41028CD5"
+    );
+    assert_eq!(key.public_key().key_id(), "8DFC5FCE41028CD5");
+
+    // Keys survive being exported and read back
+    let keyring = DebKeyring::from_buf(key.armored_public_key().as_bytes())?;
+    assert_eq!(keyring.keys(), &vec![key.public_key().clone()]);
+    let imported = DebSigningKey::from_buf(key.armored_secret_key().as_bytes())?;
+    assert_eq!(imported.public_key(), key.public_key());
+
+    let signature = imported.sign(b"data");
+    assert_eq!(
+        keyring.verify(b"data", signature.as_bytes())?,
+        key.public_key()
+    );
+    assert!(matches!(
+        keyring.verify(b"tampered", signature.as_bytes()),
+        Err(DebError::BadSignature)
+    ));
+    assert!(matches!(
+        DebKeyring::new().verify(b"data", signature.as_bytes()),
+        Err(DebError::UnknownSigningKey(id)) if id == key.public_key().fingerprint()
+    ));
+
+    // Any signature by a trusted key is enough, and a bad one is reported over unknown keys
+    let other = DebSigningKey::from_seed([8; 32], "Other <other@example.com>", 1700000000);
+    let signatures = [other.sign_binary(b"data"), key.sign_binary(b"data")].concat();
+    assert_eq!(keyring.verify(b"data", &signatures)?, key.public_key());
+    assert!(matches!(
+        keyring.verify(b"tampered", &signatures),
+        Err(DebError::BadSignature)
+    ));
+
+    let text = "Origin: Test\n-----BEGIN PGP SIGNATURE-----\nTrailing: space \n";
+    let clearsigned = key.clearsign(text);
+    assert!(clearsigned.contains("\n- -----BEGIN PGP SIGNATURE-----\n"));
+    assert_eq!(keyring.verify_clearsigned(&clearsigned)?.0, text);
+    assert!(keyring
+        .verify_clearsigned(&clearsigned.replace("Test", "Evil"))
+        .is_err());
+
+    let dist = test_dir("openpgp-signatures")?;
+    repository::DebRelease::new("stable").write_signed_to_dir(&dist, &key)?;
+    let release = fs::read(dist.join("Release"))?;
+    keyring.verify(&release, &fs::read(dist.join("Release.gpg"))?)?;
+    let (inrelease, _) =
+        keyring.verify_clearsigned(&fs::read_to_string(dist.join("InRelease"))?)?;
+    assert_eq!(inrelease.as_bytes(), release);
+
+    fs::remove_dir_all(&dist)?;
+    Ok(())
+}
+
+#[test]
+fn gpg_signatures() -> std::io::Result<()> {
+    // Made by gpg 2.2 with an Ed25519 key, which also has a Curve25519
+    // encryption subkey. InRelease is signed by a second, unknown key first.
+    let keyring = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/key.asc"))?;
+    let release = include_bytes!("../testdata/openpgp/Release");
+    assert_eq!(keyring.keys().len(), 1);
+    let key = &keyring.keys()[0];
+    assert_eq!(
+        key.fingerprint(),
+        "2D282276B282E2A8231E35AAECEC197E2F29466F"
+    );
+    assert_eq!(
+        key.user_ids(),
+        &vec!["deb-rust fixture <fixture@example.com>".to_string()]
+    );
+
+    let signature = include_bytes!("../testdata/openpgp/Release.gpg");
+    assert_eq!(keyring.verify(release, signature)?, key);
+    assert!(matches!(
+        keyring.verify(&release[1..], signature),
+        Err(DebError::BadSignature)
+    ));
+
+    let inrelease = include_str!("../testdata/openpgp/InRelease");
+    let (text, signer) = keyring.verify_clearsigned(inrelease)?;
+    assert_eq!(signer, key);
+    assert_eq!(text.as_bytes(), release);
+    assert!(matches!(
+        DebKeyring::new().verify_clearsigned(inrelease),
+        Err(DebError::UnknownSigningKey(_))
+    ));
+
+    // Text signatures ignore line endings and trailing whitespace
+    let signature = include_bytes!("../testdata/openpgp/Release.text.gpg");
+    assert_eq!(keyring.verify(release, signature)?, key);
+    let crlf = String::from_utf8_lossy(release).replace('\n', " \r\n");
+    assert_eq!(keyring.verify(crlf.as_bytes(), signature)?, key);
+    assert!(matches!(
+        keyring.verify(
+            crlf.as_bytes(),
+            include_bytes!("../testdata/openpgp/Release.gpg")
+        ),
+        Err(DebError::BadSignature)
+    ));
+    Ok(())
+}
+
+#[test]
+fn gpg_keyrings() -> std::io::Result<()> {
+    // The fixtures were made by gpg 2.2, and gpgv agrees with each result
+    let release = include_bytes!("../testdata/openpgp/Release");
+    let fixture = "2D282276B282E2A8231E35AAECEC197E2F29466F";
+
+    // Only the signing subkey is kept when the primary key can only certify
+    let keyring = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/subkey.asc"))?;
+    assert_eq!(keyring.keys().len(), 1);
+    let key = &keyring.keys()[0];
+    assert_eq!(
+        key.fingerprint(),
+        "9AA0965F4DF62682C15D49F3434A804A96D40289"
+    );
+    assert_eq!(
+        key.user_ids(),
+        &vec!["deb-rust subkeys <subkeys@example.com>".to_string()]
+    );
+    let signature = include_bytes!("../testdata/openpgp/Release.subkey.gpg");
+    assert_eq!(keyring.verify(release, signature)?, key);
+    assert!(matches!(
+        keyring.verify(
+            release,
+            include_bytes!("../testdata/openpgp/Release.sigexpired.gpg")
+        ),
+        Err(DebError::ExpiredSignature)
+    ));
+
+    let revoked = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/subkey-revoked.asc"))?;
+    assert!(revoked.keys()[0].revoked());
+    assert!(matches!(
+        revoked.verify(release, signature),
+        Err(DebError::RevokedKey(_))
+    ));
+
+    // A subkey with a binding signature from another key isn't trusted
+    let unbound = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/unbound.gpg"))?;
+    assert_eq!(unbound.keys().len(), 1);
+    assert_eq!(unbound.keys()[0].fingerprint(), fixture);
+    assert!(matches!(
+        unbound.verify(release, signature),
+        Err(DebError::UnknownSigningKey(_))
+    ));
+
+    let expired = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/expired.asc"))?;
+    assert!(expired.keys()[0].expires().is_some());
+    assert!(matches!(
+        expired.verify(
+            release,
+            include_bytes!("../testdata/openpgp/Release.expired.gpg")
+        ),
+        Err(DebError::ExpiredKey(_))
+    ));
+
+    let revoked = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/revoked.asc"))?;
+    assert!(matches!(
+        revoked.verify(release, include_bytes!("../testdata/openpgp/Release.gpg")),
+        Err(DebError::RevokedKey(_))
+    ));
+
+    // Certificates with RSA primary keys are skipped, along with their subkeys,
+    // without affecting the certificates after them
+    let keyring = DebKeyring::from_buf(include_bytes!("../testdata/openpgp/rsa.asc"))?;
+    assert_eq!(keyring.keys().len(), 1);
+    assert_eq!(keyring.keys()[0].fingerprint(), fixture);
+    assert_eq!(
+        keyring.keys()[0].user_ids(),
+        &vec!["deb-rust fixture <fixture@example.com>".to_string()]
+    );
+    assert!(matches!(
+        keyring.verify(
+            release,
+            include_bytes!("../testdata/openpgp/Release.rsa.gpg")
+        ),
+        Err(DebError::UnknownSigningKey(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn package_signatures() -> std::io::Result<()> {
+    let origin = DebSigningKey::from_seed([1; 32], "Origin <origin@example.com>", 0);
+    let builder = DebSigningKey::from_seed([2; 32], "Builder <builder@example.com>", 0);
+    let keyring = DebKeyring::new()
+        .with_key(origin.public_key().clone())
+        .with_key(builder.public_key().clone());
+
+    let mut archive = DebPackage::new("test").build()?;
+    let mut unsigned: Vec<u8> = Vec::new();
+    archive.write(&mut unsigned)?;
+    assert!(archive.verify_signatures(&keyring)?.is_empty());
+    archive.sign(DebSignatureKind::Origin, &origin);
+    archive.sign(DebSignatureKind::Builder, &builder);
+    let mut signed: Vec<u8> = Vec::new();
+    archive.write(&mut signed)?;
+
+    // Signatures survive being written and read back
+    let archive = DebArchive::read(signed.as_slice())?;
+    let signatures = archive.verify_signatures(&keyring)?;
+    assert_eq!(signatures.len(), 2);
+    assert_eq!(signatures[0].kind(), DebSignatureKind::Origin);
+    assert_eq!(signatures[0].key(), origin.public_key());
+    assert_eq!(signatures[1].kind(), DebSignatureKind::Builder);
+    assert_eq!(signatures[1].key(), builder.public_key());
+    let manifest = String::from_utf8_lossy(&archive.extra_members()[1].1).into_owned();
+    assert!(manifest.contains("Role: builder\n"));
+    assert!(manifest.contains(" 4 debian-binary\n"));
+    assert!(matches!(
+        archive.verify_signature(DebSignatureKind::Origin, &DebKeyring::new()),
+        Err(DebError::UnknownSigningKey(_))
+    ));
+
+    // Signatures moved to another package don't match it
+    let mut other: Vec<u8> = Vec::new();
+    DebPackage::new("other").build()?.write(&mut other)?;
+    other.extend(&signed[unsigned.len()..]);
+    let other = DebArchive::read(other.as_slice())?;
+    for kind in [DebSignatureKind::Origin, DebSignatureKind::Builder] {
+        assert!(matches!(
+            other.verify_signature(kind, &keyring),
+            Err(DebError::BadSignature)
+        ));
+    }
+    Ok(())
+}
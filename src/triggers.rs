@@ -0,0 +1,162 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fmt;
+
+use crate::error::DebError;
+
+/// A directive in a package's `triggers` control file.
+///
+/// Interest directives declare triggers the package handles, and activate
+/// directives declare triggers the package activates when it's installed or
+/// upgraded. The `Await` and `Noawait` variants correspond to the `-await` and
+/// `-noawait` suffixes, which control whether the activating package must wait
+/// for the trigger to be processed.
+///
+/// Read [deb-triggers(5)][1] for more details.
+///
+/// [1]: https://manpages.debian.org/unstable/dpkg-dev/deb-triggers.5.en.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebTriggerDirective {
+    /// `interest`
+    Interest,
+    /// `interest-await`
+    InterestAwait,
+    /// `interest-noawait`
+    InterestNoawait,
+    /// `activate`
+    Activate,
+    /// `activate-await`
+    ActivateAwait,
+    /// `activate-noawait`
+    ActivateNoawait,
+}
+
+impl DebTriggerDirective {
+    /// Returns the directive as it appears in a triggers file.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DebTriggerDirective::Interest => "interest",
+            DebTriggerDirective::InterestAwait => "interest-await",
+            DebTriggerDirective::InterestNoawait => "interest-noawait",
+            DebTriggerDirective::Activate => "activate",
+            DebTriggerDirective::ActivateAwait => "activate-await",
+            DebTriggerDirective::ActivateNoawait => "activate-noawait",
+        }
+    }
+
+    /// Parses a DebTriggerDirective from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` isn't a known directive.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        match input {
+            "interest" => Ok(DebTriggerDirective::Interest),
+            "interest-await" => Ok(DebTriggerDirective::InterestAwait),
+            "interest-noawait" => Ok(DebTriggerDirective::InterestNoawait),
+            "activate" => Ok(DebTriggerDirective::Activate),
+            "activate-await" => Ok(DebTriggerDirective::ActivateAwait),
+            "activate-noawait" => Ok(DebTriggerDirective::ActivateNoawait),
+            &_ => Err(DebError::InvalidMember {
+                member: "triggers".to_string(),
+                line: 1,
+                reason: format!("unknown trigger directive {}", input),
+            }),
+        }
+    }
+}
+
+/// A single line of a package's `triggers` control file, such as
+/// `interest-noawait /usr/share/icons`.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::{DebTrigger, DebTriggerDirective};
+///
+/// let trigger = DebTrigger::new(DebTriggerDirective::ActivateNoawait, "ldconfig");
+/// assert_eq!(trigger.to_string(), "activate-noawait ldconfig");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebTrigger {
+    directive: DebTriggerDirective, // Whether the trigger is handled or activated
+    name: String,                   // The trigger's name, or a file path for file triggers
+}
+
+impl DebTrigger {
+    /// Creates a DebTrigger with `directive` for the trigger `name`.
+    pub fn new(directive: DebTriggerDirective, name: &str) -> Self {
+        Self {
+            directive,
+            name: name.to_string(),
+        }
+    }
+
+    /// Parses a DebTrigger from a single line of a triggers file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directive is unknown, or if the
+    /// line doesn't consist of a directive followed by a trigger name.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        let mut words = input.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(directive), Some(name), None) => {
+                Ok(Self::new(DebTriggerDirective::from(directive)?, name))
+            }
+            _ => Err(DebError::InvalidMember {
+                member: "triggers".to_string(),
+                line: 1,
+                reason: format!("invalid trigger {}", input.trim()),
+            }),
+        }
+    }
+
+    /// Parses every trigger in the contents of a triggers file.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any trigger in `input` is invalid.
+    pub fn from_list(input: &str) -> Result<Vec<Self>, DebError> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(index, line)| Self::from(line).map_err(|e| e.in_member("triggers", index + 1)))
+            .collect()
+    }
+
+    /// Returns the trigger's directive.
+    pub fn directive(&self) -> &DebTriggerDirective {
+        &self.directive
+    }
+
+    /// Returns the trigger's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for DebTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.directive.as_str(), self.name)
+    }
+}
@@ -0,0 +1,229 @@
+/*
+    deb-rust - Rust library for building and reading Deb packages
+    Copyright (C) 2023  NotSludgeBomb
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    This program is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::error::DebError;
+
+/// A package version, in the format `[epoch:]upstream_version[-debian_revision]`.
+///
+/// DebVersions are compared the same way dpkg compares them, so `1.0~rc1` is
+/// earlier than `1.0`, and `1:0.9` is later than both. Versions which dpkg
+/// considers equal, such as `1.0` and `0:1.0-0`, are equal.
+///
+/// Read Debian's documentation on the [Version field][1] for more details.
+///
+/// # Example
+///
+/// ```
+/// use deb_rust::DebVersion;
+///
+/// let release = DebVersion::from("1.0-1").unwrap();
+/// let candidate = DebVersion::from("1.0~rc1-1").unwrap();
+/// assert!(candidate < release);
+/// ```
+///
+/// [1]: https://www.debian.org/doc/debian-policy/ch-controlfields.html#version
+#[derive(Debug, Clone)]
+pub struct DebVersion {
+    epoch: u32,       // Defaults to 0 when not present
+    upstream: String, // The main part of the version
+    revision: String, // Empty for native packages
+}
+
+impl DebVersion {
+    /// Parses a DebVersion from `input`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `input` is empty, has an invalid
+    /// epoch, doesn't start with a digit, or contains characters not allowed in
+    /// a version.
+    pub fn from(input: &str) -> Result<Self, DebError> {
+        let invalid = |reason: &str| DebError::InvalidVersion {
+            version: input.to_string(),
+            reason: reason.to_string(),
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(invalid("version string is empty"));
+        }
+        if input.contains(char::is_whitespace) {
+            return Err(invalid("version string has embedded spaces"));
+        }
+
+        let (epoch, rest) = match input.split_once(':') {
+            Some((epoch, rest)) => {
+                if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid("version epoch is invalid"));
+                }
+                match epoch.parse::<u32>() {
+                    Ok(epoch) => (epoch, rest),
+                    Err(_) => return Err(invalid("version epoch is too big")),
+                }
+            }
+            None => (0, input),
+        };
+
+        let (upstream, revision) = match rest.rsplit_once('-') {
+            Some((upstream, revision)) => (upstream, revision),
+            None => (rest, ""),
+        };
+        if upstream.is_empty() {
+            return Err(invalid("upstream version is empty"));
+        }
+        if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(invalid("version number does not start with a digit"));
+        }
+        if upstream
+            .chars()
+            .any(|c| !c.is_ascii_alphanumeric() && !".+~-:".contains(c))
+        {
+            return Err(invalid("invalid character in version number"));
+        }
+        if rest.contains('-') && revision.is_empty() {
+            return Err(invalid("revision number is empty"));
+        }
+        if revision
+            .chars()
+            .any(|c| !c.is_ascii_alphanumeric() && !".+~".contains(c))
+        {
+            return Err(invalid("invalid character in revision number"));
+        }
+
+        Ok(Self {
+            epoch,
+            upstream: upstream.to_string(),
+            revision: revision.to_string(),
+        })
+    }
+
+    /// Returns the version's epoch.
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Returns the version's upstream version.
+    pub fn upstream(&self) -> &str {
+        &self.upstream
+    }
+
+    /// Returns the version's Debian revision, which is empty for native packages.
+    pub fn revision(&self) -> &str {
+        &self.revision
+    }
+}
+
+impl fmt::Display for DebVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}:", self.epoch)?;
+        }
+        write!(f, "{}", self.upstream)?;
+        if !self.revision.is_empty() {
+            write!(f, "-{}", self.revision)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for DebVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| verrevcmp(&self.upstream, &other.upstream))
+            .then_with(|| verrevcmp(&self.revision, &other.revision))
+    }
+}
+
+impl PartialOrd for DebVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for DebVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DebVersion {}
+
+// Weight of a single non-digit character, as in dpkg's `order()`.
+// Tildes sort before everything, even the end of the string, and letters
+// sort before any other character.
+fn order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(b'~') => -1,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+// A port of dpkg's `verrevcmp()`, which compares alternating runs of
+// non-digits and digits from left to right
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let is_digit = |s: &[u8], i: usize| s.get(i).is_some_and(u8::is_ascii_digit);
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() || j < b.len() {
+        let mut first_diff = 0;
+
+        while (i < a.len() && !is_digit(a, i)) || (j < b.len() && !is_digit(b, j)) {
+            let ac = order(a.get(i).copied());
+            let bc = order(b.get(j).copied());
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+        while is_digit(a, i) && is_digit(b, j) {
+            if first_diff == 0 {
+                first_diff = a[i] as i32 - b[j] as i32;
+            }
+            i += 1;
+            j += 1;
+        }
+
+        if is_digit(a, i) {
+            return Ordering::Greater;
+        }
+        if is_digit(b, j) {
+            return Ordering::Less;
+        }
+        if first_diff != 0 {
+            return first_diff.cmp(&0);
+        }
+    }
+
+    Ordering::Equal
+}